@@ -0,0 +1,71 @@
+//! `cargo bench --target x86_64-unknown-linux-gnu --no-default-features` などホスト向けに実行する。
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sudoku::*;
+
+use sudoku_app::{board::BoardExt, solver};
+
+/// 既知の難問(いずれも解は一意)。解と難易度は `tests/fixtures.rs` で確かめている。
+const HARD_PUZZLES: &[(&str, &str)] = &[
+    (
+        "17-clue (Royle #1)",
+        "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+    ),
+    (
+        "17-clue (Royle #2)",
+        "000000012000035000000600070700000300000400800100000000000120000080000040050000600",
+    ),
+    (
+        "AI Escargot",
+        "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+    ),
+    (
+        "Inkala 2012",
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+    ),
+];
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_flexible");
+    for &(name, s) in HARD_PUZZLES {
+        group.bench_function(name, |b| {
+            b.iter(|| Board::parse_flexible(black_box(s)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    for &(name, s) in HARD_PUZZLES {
+        let board = Board::parse_flexible(s).unwrap();
+        group.bench_function(name, |b| b.iter(|| solver::solve(black_box(&board))));
+    }
+    group.finish();
+}
+
+fn bench_count_solutions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("count_solutions(2)");
+    for &(name, s) in HARD_PUZZLES {
+        let board = Board::parse_flexible(s).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| solver::count_solutions(black_box(&board), 2))
+        });
+    }
+    group.finish();
+}
+
+fn bench_generate(c: &mut Criterion) {
+    c.bench_function("generate_unique(35)", |b| {
+        b.iter(|| Sudoku::generate_unique(black_box(35)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_solve,
+    bench_count_solutions,
+    bench_generate
+);
+criterion_main!(benches);
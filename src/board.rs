@@ -0,0 +1,903 @@
+use std::fmt;
+
+use sudoku::*;
+
+use crate::number_set::NumberSet;
+use crate::square::{block_squares, col_squares, row_squares, SquareExt};
+use crate::symmetry::{Symmetry, SymmetrySet};
+
+pub trait BoardExt: Sized {
+    /// 盤面テキストを寛容にパースする。
+    ///
+    /// 空白と `,` は無視し、`1`-`9` を数字、`.`/`0`/`_` を空マスとみなす。
+    /// セルがちょうど 81 個でなければエラー。
+    fn parse_flexible(s: &str) -> Result<Self, ParseBoardError>;
+
+    /// `to_line` の形式を厳密にパースする。
+    ///
+    /// ちょうど 81 文字で、各文字が `1`-`9` または空マスの `.`/`0` でなければエラー。空白も許さない。
+    fn from_line(s: &str) -> Result<Self, LineParseError>;
+
+    /// 空の盤面に `(sq, num)` を順に置いた盤面を返す。
+    ///
+    /// 同じマスが 2 回以上現れたら panic する。`FromIterator` は sudoku クレート側でないと実装できないため別名。
+    fn from_placements(placements: impl IntoIterator<Item = (Square, Number)>) -> Self;
+
+    /// 解 `solution` の盤面を返す。
+    ///
+    /// 生成系の関数(`Sudoku::generate_unique` など)は解を、すべてのマスが埋まった `Sudoku` として返す。
+    /// 解を盤面として扱う `Game` などに渡すときに使う。`Board::empty()` はこれと逆に全マスが `None`。
+    /// `solution` が埋まりきっていなければ panic する。
+    fn from_solution(solution: &Sudoku) -> Self;
+
+    /// 盤面を `Square::all()` 順の 81 文字(空マスは `.`)にする。`from_line` で厳密に読み戻せる。
+    fn to_line(&self) -> String;
+
+    /// `+`/`-`/`|` で枠線を描いた盤面テキストをパースする。
+    ///
+    /// 枠線の文字を取り除いたうえで `parse_flexible` と同じ規則で読む。`to_grid` の出力を読み戻せる。
+    fn parse_grid(s: &str) -> Result<Self, ParseBoardError>;
+
+    /// 盤面を、ブロックの境界に枠線を入れた 13 行のテキストにする(空マスは `.`)。
+    fn to_grid(&self) -> String;
+
+    /// 数字 `n` を `mapping[n - 1]` に置き換えた盤面を返す。
+    ///
+    /// `mapping` が 1-9 の並べ替えなら、矛盾のない盤面(解盤面を含む)は矛盾のないまま保たれる。
+    fn relabel(&self, mapping: [Number; 9]) -> Self;
+
+    /// 全マスを `(sq, self[sq])` として `Square::all()` 順に走査する。
+    fn iter(&self) -> Cells<'_>;
+
+    /// `self` と `other` で値が異なるマスを `(sq, self[sq], other[sq])` として `Square::all()` 順に返す。
+    fn diff(&self, other: &Board) -> Vec<(Square, Option<Number>, Option<Number>)>;
+
+    /// `sq` 以外で `sq` と行/列/ブロックを共有するマスに `num` がなければ `true`。
+    fn can_put(&self, sq: Square, num: Number) -> bool;
+
+    /// `sq` に `num` を置いたとき重複する、`num` の入った `sq` 以外のマスを `Square::all()` 順に返す。
+    ///
+    /// `can_put` が `true` なら空。
+    fn conflicts(&self, sq: Square, num: Number) -> Vec<Square>;
+
+    /// 空マスがなければ `true`。
+    fn is_full(&self) -> bool;
+
+    /// `sq` の次の空マスを行優先で(右へ、行末からは次の行の左端へ)探す。`reverse` なら逆向き。
+    ///
+    /// 盤面の端では反対の端へ折り返す(右下の次は左上)。`sq` 以外に空マスがなければ `None`。
+    fn next_empty_square(&self, sq: Square, reverse: bool) -> Option<Square>;
+
+    /// 行/列/ブロックのどこにも同じ数字が重複していなければ `true`(空マスは問わない)。
+    fn is_valid(&self) -> bool;
+
+    /// 行 `row` のマスを左から順に `(sq, self[sq])` として返す。
+    fn row_cells(&self, row: Row) -> [(Square, Option<Number>); 9];
+
+    /// 列 `col` のマスを上から順に `(sq, self[sq])` として返す。
+    fn col_cells(&self, col: Col) -> [(Square, Option<Number>); 9];
+
+    /// ブロック `block` のマスを左上から行優先で `(sq, self[sq])` として返す。
+    fn block_cells(&self, block: Block) -> [(Square, Option<Number>); 9];
+
+    /// 行 `row` が 1-9 で重複なく埋まっていれば `true`。
+    fn is_row_complete(&self, row: Row) -> bool;
+
+    /// 列 `col` が 1-9 で重複なく埋まっていれば `true`。
+    fn is_col_complete(&self, col: Col) -> bool;
+
+    /// ブロック `block` が 1-9 で重複なく埋まっていれば `true`。
+    fn is_block_complete(&self, block: Block) -> bool;
+
+    /// 行ごとの埋まっているマスの数。添字は `Row::get`。
+    fn givens_per_row(&self) -> [u8; 9];
+
+    /// 列ごとの埋まっているマスの数。添字は `Col::get`。
+    fn givens_per_col(&self) -> [u8; 9];
+
+    /// ブロックごとの埋まっているマスの数。添字は `Block::get`。
+    ///
+    /// 問題数字が 1 つもないユニットがあると、難しいか解が一意でない問題になりやすい。
+    fn givens_per_block(&self) -> [u8; 9];
+
+    /// 空マス `sq` に置ける数字の集合を返す。`sq` が埋まっていれば空。
+    fn candidates(&self, sq: Square) -> NumberSet;
+
+    /// `candidates` を小さい順に並べた `Vec`。`sq` が埋まっていれば空。
+    fn candidate_list(&self, sq: Square) -> Vec<Number>;
+
+    /// 候補がちょうど 1 つの空マスすべて(ネイキッドシングル)を `(sq, num)` として `Square::all()` 順に返す。
+    ///
+    /// `techniques::naked_single` と違い、最初の 1 つだけでなく現時点のものをまとめて返す。
+    fn forced_cells(&self) -> Vec<(Square, Number)>;
+
+    /// 埋まっているマスの配置が持つ対称性を返す。数字の値は問わない。
+    fn symmetry(&self) -> SymmetrySet;
+
+    /// `HashMap` などのキーに使える、盤面の内容だけで決まる値を返す。
+    ///
+    /// `Board` 自体への `Hash`/`Eq` の実装は sudoku クレート側でないとできないため代わりに使う。
+    fn key(&self) -> BoardKey;
+}
+
+impl BoardExt for Board {
+    fn parse_flexible(s: &str) -> Result<Self, ParseBoardError> {
+        let mut cells = Vec::with_capacity(81);
+        for ch in s.chars() {
+            match ch {
+                _ if ch.is_whitespace() => {}
+                ',' => {}
+                '.' | '0' | '_' => cells.push(None),
+                '1'..='9' => cells.push(Some(number_from_digit(ch))),
+                _ => return Err(ParseBoardError::InvalidChar { ch }),
+            }
+        }
+
+        if cells.len() != 81 {
+            return Err(ParseBoardError::CellCount { count: cells.len() });
+        }
+
+        let mut board = Board::empty();
+        for (sq, cell) in Square::all().into_iter().zip(cells) {
+            board[sq] = cell;
+        }
+
+        Ok(board)
+    }
+
+    fn from_line(s: &str) -> Result<Self, LineParseError> {
+        let len = s.chars().count();
+        if len != 81 {
+            return Err(LineParseError::Length { len });
+        }
+
+        let mut board = Board::empty();
+        for (index, (sq, ch)) in Square::all().into_iter().zip(s.chars()).enumerate() {
+            board[sq] = match ch {
+                '.' | '0' => None,
+                '1'..='9' => Some(number_from_digit(ch)),
+                _ => return Err(LineParseError::InvalidChar { index, ch }),
+            };
+        }
+
+        Ok(board)
+    }
+
+    fn from_placements(placements: impl IntoIterator<Item = (Square, Number)>) -> Self {
+        let mut board = Board::empty();
+        for (sq, num) in placements {
+            assert!(board[sq].is_none(), "duplicate square: {sq:?}");
+            board[sq] = Some(num);
+        }
+        board
+    }
+
+    fn from_solution(solution: &Sudoku) -> Self {
+        let board = solution.board();
+        assert!(board.is_full(), "solution must be a full board");
+        board.clone()
+    }
+
+    fn to_line(&self) -> String {
+        self.iter()
+            .map(|(_, cell)| cell.map_or(".".to_owned(), |num| num.get().to_string()))
+            .collect()
+    }
+
+    fn parse_grid(s: &str) -> Result<Self, ParseBoardError> {
+        let s: String = s
+            .chars()
+            .filter(|ch| !matches!(ch, '+' | '-' | '|'))
+            .collect();
+        Self::parse_flexible(&s)
+    }
+
+    fn to_grid(&self) -> String {
+        const BORDER: &str = "+-------+-------+-------+\n";
+
+        let mut s = String::from(BORDER);
+        for row in Row::all() {
+            for (i, (_, cell)) in self.row_cells(row).into_iter().enumerate() {
+                if i % 3 == 0 {
+                    s.push_str("| ");
+                }
+                s.push(cell.map_or('.', |num| char::from(b'0' + num.get())));
+                s.push(' ');
+            }
+            s.push_str("|\n");
+            if row.get() % 3 == 2 {
+                s.push_str(BORDER);
+            }
+        }
+        s
+    }
+
+    fn relabel(&self, mapping: [Number; 9]) -> Self {
+        let mut board = Board::empty();
+        for (sq, cell) in self.iter() {
+            board[sq] = cell.map(|num| mapping[usize::from(num.get()) - 1]);
+        }
+        board
+    }
+
+    fn iter(&self) -> Cells<'_> {
+        Cells {
+            board: self,
+            squares: Square::all().into_iter(),
+        }
+    }
+
+    fn diff(&self, other: &Board) -> Vec<(Square, Option<Number>, Option<Number>)> {
+        self.iter()
+            .filter(|&(sq, num)| num != other[sq])
+            .map(|(sq, num)| (sq, num, other[sq]))
+            .collect()
+    }
+
+    fn can_put(&self, sq: Square, num: Number) -> bool {
+        sq.peers().into_iter().all(|peer| self[peer] != Some(num))
+    }
+
+    fn conflicts(&self, sq: Square, num: Number) -> Vec<Square> {
+        sq.peers()
+            .into_iter()
+            .filter(|&peer| self[peer] == Some(num))
+            .collect()
+    }
+
+    fn is_full(&self) -> bool {
+        self.iter().all(|(_, cell)| cell.is_some())
+    }
+
+    fn next_empty_square(&self, sq: Square, reverse: bool) -> Option<Square> {
+        let all = Square::all();
+        let i = sq.index();
+        (1..81)
+            .map(|k| {
+                if reverse {
+                    (i + 81 - k) % 81
+                } else {
+                    (i + k) % 81
+                }
+            })
+            .map(|j| all[j])
+            .find(|&next| self[next].is_none())
+    }
+
+    fn is_valid(&self) -> bool {
+        self.iter().all(|(sq, cell)| match cell {
+            Some(num) => self.can_put(sq, num),
+            None => true,
+        })
+    }
+
+    fn row_cells(&self, row: Row) -> [(Square, Option<Number>); 9] {
+        row_squares(row).map(|sq| (sq, self[sq]))
+    }
+
+    fn col_cells(&self, col: Col) -> [(Square, Option<Number>); 9] {
+        col_squares(col).map(|sq| (sq, self[sq]))
+    }
+
+    fn block_cells(&self, block: Block) -> [(Square, Option<Number>); 9] {
+        block_squares(block).map(|sq| (sq, self[sq]))
+    }
+
+    fn is_row_complete(&self, row: Row) -> bool {
+        is_unit_complete(&self.row_cells(row))
+    }
+
+    fn is_col_complete(&self, col: Col) -> bool {
+        is_unit_complete(&self.col_cells(col))
+    }
+
+    fn is_block_complete(&self, block: Block) -> bool {
+        is_unit_complete(&self.block_cells(block))
+    }
+
+    fn givens_per_row(&self) -> [u8; 9] {
+        Row::all().map(|row| count_filled(&self.row_cells(row)))
+    }
+
+    fn givens_per_col(&self) -> [u8; 9] {
+        Col::all().map(|col| count_filled(&self.col_cells(col)))
+    }
+
+    fn givens_per_block(&self) -> [u8; 9] {
+        Block::all().map(|block| count_filled(&self.block_cells(block)))
+    }
+
+    fn candidates(&self, sq: Square) -> NumberSet {
+        if self[sq].is_some() {
+            return NumberSet::new();
+        }
+
+        Number::all()
+            .into_iter()
+            .filter(|&num| self.can_put(sq, num))
+            .collect()
+    }
+
+    fn candidate_list(&self, sq: Square) -> Vec<Number> {
+        self.candidates(sq).iter().collect()
+    }
+
+    fn forced_cells(&self) -> Vec<(Square, Number)> {
+        self.iter()
+            .filter(|&(_, cell)| cell.is_none())
+            .filter_map(|(sq, _)| {
+                let candidates = self.candidates(sq);
+                match candidates.len() {
+                    1 => candidates.iter().next().map(|num| (sq, num)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn symmetry(&self) -> SymmetrySet {
+        Symmetry::ALL
+            .into_iter()
+            .filter(|&symmetry| {
+                Square::all()
+                    .into_iter()
+                    .all(|sq| self[sq].is_some() == self[symmetry.map(sq)].is_some())
+            })
+            .collect()
+    }
+
+    fn key(&self) -> BoardKey {
+        let mut cells = [0; 81];
+        for (cell, (_, num)) in cells.iter_mut().zip(self.iter()) {
+            *cell = num.map_or(0, |num| num.get());
+        }
+        BoardKey(cells)
+    }
+}
+
+/// `BoardExt::key` の戻り値。各マスの数字(空マスは 0)を `Square::all()` 順に並べたもの。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BoardKey([u8; 81]);
+
+/// `BoardExt::iter` の戻り値。
+#[derive(Clone, Debug)]
+pub struct Cells<'a> {
+    board: &'a Board,
+    squares: std::array::IntoIter<Square, 81>,
+}
+
+impl Iterator for Cells<'_> {
+    type Item = (Square, Option<Number>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.squares.next().map(|sq| (sq, self.board[sq]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.squares.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Cells<'_> {}
+
+fn is_unit_complete(cells: &[(Square, Option<Number>); 9]) -> bool {
+    let nums: NumberSet = cells.iter().filter_map(|&(_, num)| num).collect();
+    nums == NumberSet::all()
+}
+
+fn count_filled(cells: &[(Square, Option<Number>); 9]) -> u8 {
+    cells.iter().filter(|(_, num)| num.is_some()).count() as u8
+}
+
+fn number_from_digit(ch: char) -> Number {
+    let d = ch.to_digit(10).unwrap();
+    Number::all()[d as usize - 1]
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseBoardError {
+    InvalidChar { ch: char },
+    CellCount { count: usize },
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidChar { ch } => write!(f, "invalid character: {ch:?}"),
+            Self::CellCount { count } => write!(f, "expected 81 cells, got {count}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+/// `BoardExt::from_line` のエラー。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LineParseError {
+    /// 文字数(バイト数ではない)が 81 でない。
+    Length { len: usize },
+    /// `index` 文字目(0 始まり)の `ch` はマスとして読めない。
+    InvalidChar { index: usize, ch: char },
+}
+
+impl fmt::Display for LineParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Length { len } => write!(f, "expected 81 characters, got {len}"),
+            Self::InvalidChar { index, ch } => {
+                write!(f, "invalid character at index {index}: {ch:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LineParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn puzzle() -> Board {
+        Board::from_line(PUZZLE).unwrap()
+    }
+
+    fn solution() -> Board {
+        Board::from_line(SOLUTION).unwrap()
+    }
+
+    fn sq(index: usize) -> Square {
+        Square::all()[index]
+    }
+
+    fn num(n: u8) -> Number {
+        Number::all()[usize::from(n) - 1]
+    }
+
+    #[test]
+    fn diff_of_equal_boards_is_empty() {
+        assert!(puzzle().diff(&puzzle()).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_single_placement() {
+        let before = puzzle();
+        let mut after = puzzle();
+        after[sq(2)] = Some(num(4));
+        assert_eq!(before.diff(&after), [(sq(2), None, Some(num(4)))]);
+        assert_eq!(after.diff(&before), [(sq(2), Some(num(4)), None)]);
+    }
+
+    #[test]
+    fn diff_is_in_square_order() {
+        let diff = puzzle().diff(&solution());
+        assert_eq!(diff.len(), PUZZLE.matches('.').count());
+        assert!(diff.windows(2).all(|w| w[0].0.index() < w[1].0.index()));
+        assert!(diff
+            .iter()
+            .all(|&(sq, old, new)| old.is_none() && new == solution()[sq]));
+    }
+
+    #[test]
+    fn parse_flexible_accepts_81_chars() {
+        assert_eq!(Board::parse_flexible(PUZZLE).unwrap().key(), puzzle().key());
+    }
+
+    #[test]
+    fn parse_flexible_accepts_zeros_and_underscores() {
+        let zeros = PUZZLE.replace('.', "0");
+        let underscores = PUZZLE.replace('.', "_");
+        assert_eq!(Board::parse_flexible(&zeros).unwrap().key(), puzzle().key());
+        assert_eq!(
+            Board::parse_flexible(&underscores).unwrap().key(),
+            puzzle().key()
+        );
+    }
+
+    #[test]
+    fn parse_flexible_accepts_spaces_and_commas() {
+        let spaced: Vec<String> = PUZZLE.chars().map(String::from).collect();
+        let spaces = spaced.join(" ");
+        let commas = spaced.join(", ");
+        assert_eq!(
+            Board::parse_flexible(&spaces).unwrap().key(),
+            puzzle().key()
+        );
+        assert_eq!(
+            Board::parse_flexible(&commas).unwrap().key(),
+            puzzle().key()
+        );
+    }
+
+    #[test]
+    fn parse_flexible_accepts_nine_lines() {
+        let lines: Vec<&str> = (0..9).map(|r| &PUZZLE[r * 9..r * 9 + 9]).collect();
+        let s = format!("\n{}\n", lines.join("\r\n"));
+        assert_eq!(Board::parse_flexible(&s).unwrap().key(), puzzle().key());
+    }
+
+    #[test]
+    fn parse_flexible_rejects_wrong_cell_count() {
+        assert_eq!(
+            Board::parse_flexible(&PUZZLE[..80]).map(|_| ()),
+            Err(ParseBoardError::CellCount { count: 80 })
+        );
+        assert_eq!(
+            Board::parse_flexible(&format!("{PUZZLE}.")).map(|_| ()),
+            Err(ParseBoardError::CellCount { count: 82 })
+        );
+        assert_eq!(
+            Board::parse_flexible("").map(|_| ()),
+            Err(ParseBoardError::CellCount { count: 0 })
+        );
+    }
+
+    #[test]
+    fn parse_flexible_rejects_invalid_char() {
+        let s = PUZZLE.replacen('.', "x", 1);
+        assert_eq!(
+            Board::parse_flexible(&s).map(|_| ()),
+            Err(ParseBoardError::InvalidChar { ch: 'x' })
+        );
+    }
+
+    #[test]
+    fn iter_visits_all_squares_in_order() {
+        let board = puzzle();
+        let cells: Vec<_> = board.iter().collect();
+        assert_eq!(board.iter().len(), 81);
+        assert_eq!(cells.len(), 81);
+        for (i, &(s, cell)) in cells.iter().enumerate() {
+            assert_eq!(s, sq(i));
+            assert_eq!(cell, board[s]);
+        }
+    }
+
+    #[test]
+    fn iter_reconstructs_board() {
+        let board = puzzle();
+        let mut rebuilt = Board::empty();
+        for (sq, cell) in board.iter() {
+            rebuilt[sq] = cell;
+        }
+        assert_eq!(rebuilt.key(), board.key());
+    }
+
+    #[test]
+    fn from_placements_sets_given_cells() {
+        let board = Board::from_placements([(sq(0), num(5)), (sq(80), num(9))]);
+        assert_eq!(board[sq(0)], Some(num(5)));
+        assert_eq!(board[sq(80)], Some(num(9)));
+        assert_eq!(board.iter().filter(|(_, cell)| cell.is_some()).count(), 2);
+    }
+
+    #[test]
+    fn from_placements_of_nothing_is_empty() {
+        assert_eq!(Board::from_placements([]).key(), Board::empty().key());
+    }
+
+    #[test]
+    fn from_placements_reconstructs_board() {
+        let board = puzzle();
+        let placements = board
+            .iter()
+            .filter_map(|(sq, cell)| cell.map(|num| (sq, num)));
+        assert_eq!(Board::from_placements(placements).key(), board.key());
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate square")]
+    fn from_placements_panics_on_duplicate_square() {
+        Board::from_placements([(sq(0), num(5)), (sq(0), num(6))]);
+    }
+
+    fn hash_of(key: BoardKey) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn key_of_equal_boards_is_equal() {
+        let a = puzzle();
+        let b = Board::parse_flexible(&PUZZLE.replace('.', "0")).unwrap();
+        assert_eq!(a.key(), b.key());
+        assert_eq!(hash_of(a.key()), hash_of(b.key()));
+    }
+
+    #[test]
+    fn key_of_different_boards_differs() {
+        let mut other = puzzle();
+        other[sq(2)] = Some(num(4));
+        assert_ne!(puzzle().key(), other.key());
+        assert_ne!(puzzle().key(), Board::empty().key());
+    }
+
+    #[test]
+    fn key_works_as_hash_map_key() {
+        let mut map = std::collections::HashMap::new();
+        map.insert(puzzle().key(), "puzzle");
+        map.insert(solution().key(), "solution");
+        assert_eq!(
+            map.get(&Board::from_line(PUZZLE).unwrap().key()),
+            Some(&"puzzle")
+        );
+        assert_eq!(
+            map.get(&Board::from_line(SOLUTION).unwrap().key()),
+            Some(&"solution")
+        );
+        assert_eq!(map.get(&Board::empty().key()), None);
+    }
+
+    #[test]
+    fn symmetry_of_rotationally_symmetric_puzzle() {
+        let symmetry = puzzle().symmetry();
+        assert!(symmetry.contains(Symmetry::Rotate180));
+        assert!(!symmetry.contains(Symmetry::MirrorHorizontal));
+        assert!(!symmetry.contains(Symmetry::MirrorVertical));
+    }
+
+    #[test]
+    fn symmetry_of_asymmetric_board() {
+        let board = Board::from_placements([(sq(0), num(1)), (sq(1), num(2))]);
+        assert!(board.symmetry().is_empty());
+    }
+
+    #[test]
+    fn symmetry_of_empty_and_full_boards_is_everything() {
+        assert_eq!(Board::empty().symmetry().len(), Symmetry::ALL.len());
+        assert_eq!(solution().symmetry().len(), Symmetry::ALL.len());
+    }
+
+    #[test]
+    fn symmetry_ignores_values() {
+        // 中央のマスだけなら、数字によらずすべての対称性を持つ。
+        let board = Board::from_placements([(sq(40), num(7))]);
+        assert_eq!(board.symmetry().len(), Symmetry::ALL.len());
+
+        // 左上と右下に異なる数字を置いても 180° 回転対称。
+        let board = Board::from_placements([(sq(0), num(1)), (sq(80), num(2))]);
+        let symmetry = board.symmetry();
+        assert!(symmetry.contains(Symmetry::Rotate180));
+        assert!(symmetry.contains(Symmetry::MirrorDiagonal));
+        assert!(!symmetry.contains(Symmetry::Rotate90));
+    }
+
+    #[test]
+    fn complete_units_of_solution() {
+        let board = solution();
+        assert!(Row::all().into_iter().all(|row| board.is_row_complete(row)));
+        assert!(Col::all().into_iter().all(|col| board.is_col_complete(col)));
+        assert!(Block::all()
+            .into_iter()
+            .all(|block| board.is_block_complete(block)));
+    }
+
+    #[test]
+    fn partial_units_are_not_complete() {
+        let mut board = solution();
+        board[sq(0)] = None;
+        assert!(!board.is_row_complete(Row::all()[0]));
+        assert!(!board.is_col_complete(Col::all()[0]));
+        assert!(!board.is_block_complete(Block::all()[0]));
+        // ほかのユニットには影響しない。
+        assert!(board.is_row_complete(Row::all()[1]));
+        assert!(board.is_col_complete(Col::all()[1]));
+        assert!(board.is_block_complete(Block::all()[1]));
+    }
+
+    #[test]
+    fn full_but_invalid_units_are_not_complete() {
+        // R1C1 と R1C2 を入れ替えると、行 1 は 1-9 のまま、列 1・2 は重複する。
+        let mut board = solution();
+        let (a, b) = (board[sq(0)], board[sq(1)]);
+        board[sq(0)] = b;
+        board[sq(1)] = a;
+        assert!(board.is_row_complete(Row::all()[0]));
+        assert!(board.is_block_complete(Block::all()[0]));
+        assert!(!board.is_col_complete(Col::all()[0]));
+        assert!(!board.is_col_complete(Col::all()[1]));
+
+        // R1C1 を R1C2 と同じ数字にすると、行 1 もブロック 1 も重複する。
+        let mut board = solution();
+        board[sq(0)] = board[sq(1)];
+        assert!(!board.is_row_complete(Row::all()[0]));
+        assert!(!board.is_block_complete(Block::all()[0]));
+    }
+
+    #[test]
+    fn row_cells_match_direct_indexing() {
+        let board = puzzle();
+        for row in Row::all() {
+            let r = usize::from(row.get());
+            let cells = board.row_cells(row);
+            for (c, &(s, cell)) in cells.iter().enumerate() {
+                assert_eq!(s, sq(r * 9 + c));
+                assert_eq!(cell, board[s]);
+            }
+        }
+    }
+
+    #[test]
+    fn col_cells_match_direct_indexing() {
+        let board = puzzle();
+        for col in Col::all() {
+            let c = usize::from(col.get());
+            let cells = board.col_cells(col);
+            for (r, &(s, cell)) in cells.iter().enumerate() {
+                assert_eq!(s, sq(r * 9 + c));
+                assert_eq!(cell, board[s]);
+            }
+        }
+    }
+
+    #[test]
+    fn block_cells_match_direct_indexing() {
+        let board = puzzle();
+        for block in Block::all() {
+            let cells = board.block_cells(block);
+            let expected: Vec<_> = Square::all()
+                .into_iter()
+                .filter(|s| s.block() == block)
+                .map(|s| (s, board[s]))
+                .collect();
+            assert_eq!(cells.to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn relabel_with_identity_keeps_board() {
+        assert_eq!(puzzle().relabel(Number::all()).key(), puzzle().key());
+    }
+
+    #[test]
+    fn relabel_maps_each_digit() {
+        // 1 → 9, 2 → 8, ..., 9 → 1。
+        let mut mapping = Number::all();
+        mapping.reverse();
+        let board = puzzle().relabel(mapping);
+        for (sq, cell) in puzzle().iter() {
+            assert_eq!(board[sq], cell.map(|n| num(10 - n.get())));
+        }
+    }
+
+    #[test]
+    fn relabeled_solution_stays_solved() {
+        let mut mapping = Number::all();
+        mapping.rotate_left(3);
+        let board = solution().relabel(mapping);
+        assert!(board.is_full());
+        assert!(board.is_valid());
+        assert!(Row::all().into_iter().all(|row| board.is_row_complete(row)));
+        assert_ne!(board.key(), solution().key());
+    }
+
+    #[test]
+    fn to_grid_round_trips_through_parse_grid() {
+        for board in [puzzle(), solution(), Board::empty()] {
+            let grid = board.to_grid();
+            assert_eq!(grid.lines().count(), 13);
+            assert_eq!(Board::parse_grid(&grid).unwrap().key(), board.key());
+        }
+    }
+
+    #[test]
+    fn to_grid_layout() {
+        let grid = puzzle().to_grid();
+        let lines: Vec<_> = grid.lines().collect();
+        assert_eq!(lines[0], "+-------+-------+-------+");
+        assert_eq!(lines[1], "| 5 3 . | . 7 . | . . . |");
+        assert_eq!(lines[4], "+-------+-------+-------+");
+    }
+
+    #[test]
+    fn parse_grid_accepts_unbordered_text() {
+        assert_eq!(Board::parse_grid(PUZZLE).unwrap().key(), puzzle().key());
+    }
+
+    #[test]
+    fn parse_grid_rejects_missing_cells() {
+        let grid = puzzle().to_grid();
+        let truncated: Vec<_> = grid.lines().take(11).collect();
+        assert_eq!(
+            Board::parse_grid(&truncated.join("\n")).map(|_| ()),
+            Err(ParseBoardError::CellCount { count: 72 })
+        );
+    }
+
+    #[test]
+    fn from_line_round_trips_random_boards() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..200 {
+            let mut board = Board::empty();
+            for sq in Square::all() {
+                let n: usize = rng.gen_range(0..10);
+                board[sq] = n.checked_sub(1).map(|i| Number::all()[i]);
+            }
+            let line = board.to_line();
+            assert_eq!(line.len(), 81);
+            assert_eq!(Board::from_line(&line).unwrap().key(), board.key());
+        }
+    }
+
+    #[test]
+    fn from_line_accepts_zeros() {
+        let zeros = PUZZLE.replace('.', "0");
+        assert_eq!(Board::from_line(&zeros).unwrap().key(), puzzle().key());
+    }
+
+    #[test]
+    fn from_line_rejects_wrong_length() {
+        assert_eq!(
+            Board::from_line(&PUZZLE[..80]).map(|_| ()),
+            Err(LineParseError::Length { len: 80 })
+        );
+        // 長さは文字数で数える。
+        let wide = format!("{}あ", &PUZZLE[..80]);
+        assert_eq!(
+            Board::from_line(&wide).map(|_| ()),
+            Err(LineParseError::InvalidChar {
+                index: 80,
+                ch: 'あ'
+            })
+        );
+    }
+
+    #[test]
+    fn from_line_rejects_what_parse_flexible_allows() {
+        let underscore = PUZZLE.replacen('.', "_", 1);
+        assert_eq!(
+            Board::from_line(&underscore).map(|_| ()),
+            Err(LineParseError::InvalidChar { index: 2, ch: '_' })
+        );
+        let spaced = format!(" {}", &PUZZLE[1..]);
+        assert_eq!(
+            Board::from_line(&spaced).map(|_| ()),
+            Err(LineParseError::InvalidChar { index: 0, ch: ' ' })
+        );
+    }
+
+    #[test]
+    fn forced_cells_finds_all_naked_singles() {
+        // 解から 3 マス空ける。互いに行/列/ブロックを共有しないので、すべて候補が 1 つになる。
+        let mut board = solution();
+        for i in [0, 40, 80] {
+            board[sq(i)] = None;
+        }
+        assert_eq!(
+            board.forced_cells(),
+            [
+                (sq(0), solution()[sq(0)].unwrap()),
+                (sq(40), solution()[sq(40)].unwrap()),
+                (sq(80), solution()[sq(80)].unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn forced_cells_matches_candidates() {
+        let board = puzzle();
+        let forced = board.forced_cells();
+        assert!(!forced.is_empty());
+        for (sq, cell) in board.iter() {
+            let candidates = board.candidates(sq);
+            let expected = (cell.is_none() && candidates.len() == 1)
+                .then(|| (sq, candidates.iter().next().unwrap()));
+            assert_eq!(forced.iter().find(|&&(s, _)| s == sq).copied(), expected);
+        }
+    }
+
+    #[test]
+    fn forced_cells_of_empty_and_full_boards() {
+        assert!(Board::empty().forced_cells().is_empty());
+        assert!(solution().forced_cells().is_empty());
+    }
+}
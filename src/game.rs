@@ -0,0 +1,1255 @@
+use std::cell::OnceCell;
+use std::fmt;
+
+use sudoku::*;
+
+use crate::board::{BoardExt, ParseBoardError};
+use crate::generate::{self, GenerationStats, PuzzleMeta};
+use crate::number_set::NumberSet;
+use crate::solver;
+use crate::square::{Label, SquareExt};
+use crate::techniques::{self, Difficulty, Technique};
+
+/// 問題数字・メモ(鉛筆書き)・確定印と、わかっていれば解を覚えている `Sudoku`。
+#[derive(Debug)]
+pub struct Game {
+    sudoku: Sudoku,
+    givens: Board,
+    /// 解盤面。生成した問題なら最初からわかっている。
+    solution: Option<Board>,
+    /// マスごとのメモ。
+    notes: [NumberSet; 81],
+    /// マスごとの、プレイヤーが自分で候補から外した数字(取り消し線で表示する)。メモとは別に持つ。
+    struck: [NumberSet; 81],
+    /// プレイヤーが確定印を付けた入力のマス。確定印が付いている間は書き換えられない。
+    confirmed: [bool; 81],
+    /// `rate` の結果。問題数字は変わらないので一度計算すれば済む。
+    difficulty: OnceCell<Difficulty>,
+}
+
+/// 複製は元のゲームと独立していて、どちらを変更してももう一方には影響しない。
+///
+/// `Sudoku` の `Clone` 実装を当てにせず、現在の盤面から作り直す。
+impl Clone for Game {
+    fn clone(&self) -> Self {
+        Self {
+            sudoku: Sudoku::new(self.board().clone()),
+            givens: self.givens.clone(),
+            solution: self.solution.clone(),
+            notes: self.notes,
+            struck: self.struck,
+            confirmed: self.confirmed,
+            difficulty: self.difficulty.clone(),
+        }
+    }
+}
+
+/// 盤面・問題数字・メモ・取り消し線・確定印がすべて同じなら等しい。これらはちょうど
+/// `to_save_string` が保存するものなので、保存して読み戻したゲームは元のゲームと等しい。
+///
+/// `Sudoku` や `Board` は `PartialEq` を実装していないので、盤面は `BoardExt::key` で比べる。
+/// 解は保存されず(問題数字から求め直せる)、難易度はキャッシュにすぎないので、どちらも比べない。
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.board().key() == other.board().key()
+            && self.givens.key() == other.givens.key()
+            && self.notes == other.notes
+            && self.struck == other.struck
+            && self.confirmed == other.confirmed
+    }
+}
+
+impl Eq for Game {}
+
+impl Game {
+    /// `sudoku` の現在の数字をすべて問題数字とみなす。解はわからないものとする。
+    pub fn new(sudoku: Sudoku) -> Self {
+        let givens = sudoku.board().clone();
+        Self {
+            sudoku,
+            givens,
+            solution: None,
+            notes: [NumberSet::new(); 81],
+            struck: [NumberSet::new(); 81],
+            confirmed: [false; 81],
+            difficulty: OnceCell::new(),
+        }
+    }
+
+    /// `new` と同じだが、解 `solution` を覚えておく(`Sudoku::generate_unique` などで解がわかっている場合)。
+    pub fn with_solution(sudoku: Sudoku, solution: Board) -> Self {
+        let mut game = Self::new(sudoku);
+        game.set_solution(solution);
+        game
+    }
+
+    pub fn board(&self) -> &Board {
+        self.sudoku.board()
+    }
+
+    /// 解盤面。`with_solution` か `set_solution` で与えていなければ `None`。
+    ///
+    /// 貼り付けや保存データから作ったゲームでは、必要になった時点で `solver::solve(game.givens())`
+    /// で求めて `set_solution` する。
+    pub fn solution(&self) -> Option<&Board> {
+        self.solution.as_ref()
+    }
+
+    pub fn set_solution(&mut self, solution: Board) {
+        self.solution = Some(solution);
+    }
+
+    pub fn givens(&self) -> &Board {
+        &self.givens
+    }
+
+    pub fn is_given(&self, sq: Square) -> bool {
+        self.givens[sq].is_some()
+    }
+
+    /// 問題の難易度(`techniques::rate`)。初回の呼び出しで計算し、以降はその値を返す。
+    pub fn rate(&self) -> Difficulty {
+        *self
+            .difficulty
+            .get_or_init(|| techniques::rate(&self.givens))
+    }
+
+    /// 問題数字と確定印の付いたマスには置けない。置けたらそのマスのメモと取り消し線は消える。
+    pub fn put(&mut self, sq: Square, num: Number) -> bool {
+        let ok = !self.is_given(sq) && !self.is_confirmed(sq) && self.sudoku.put(sq, num);
+        if ok {
+            self.notes[sq.index()] = NumberSet::new();
+            self.struck[sq.index()] = NumberSet::new();
+        }
+        ok
+    }
+
+    pub fn is_confirmed(&self, sq: Square) -> bool {
+        self.confirmed[sq.index()]
+    }
+
+    /// 入力済みのマス `sq` の確定印を付け外しする。問題数字や空マスなら何もせず `false`。
+    pub fn toggle_confirmed(&mut self, sq: Square) -> bool {
+        if self.is_given(sq) || self.board()[sq].is_none() {
+            return false;
+        }
+        self.confirmed[sq.index()] ^= true;
+        true
+    }
+
+    pub fn notes(&self, sq: Square) -> NumberSet {
+        self.notes[sq.index()]
+    }
+
+    pub fn has_note(&self, sq: Square, num: Number) -> bool {
+        self.notes[sq.index()].contains(num)
+    }
+
+    /// マス `sq` のメモ `num` を付ける(`on == false` なら消す)。
+    pub fn set_note(&mut self, sq: Square, num: Number, on: bool) {
+        if on {
+            self.notes[sq.index()].insert(num);
+        } else {
+            self.notes[sq.index()].remove(num);
+        }
+    }
+
+    /// マス `sq` で取り消し線を引いた候補。
+    pub fn struck(&self, sq: Square) -> NumberSet {
+        self.struck[sq.index()]
+    }
+
+    /// 空マス `sq` の候補 `num` の取り消し線を付け外しする。入力のあるマスなら何もせず `false`。
+    pub fn toggle_struck(&mut self, sq: Square, num: Number) -> bool {
+        if self.board()[sq].is_some() {
+            return false;
+        }
+        let struck = &mut self.struck[sq.index()];
+        if !struck.remove(num) {
+            struck.insert(num);
+        }
+        true
+    }
+
+    /// `sq` と行/列/ブロックを共有するマスのメモから `num` を外す。
+    pub fn remove_note_from_peers(&mut self, sq: Square, num: Number) {
+        for peer in sq.peers() {
+            self.set_note(peer, num, false);
+        }
+    }
+
+    /// 数字 `num` のメモを、それが候補になっている空マスすべてに付ける。
+    ///
+    /// そのようなマスすべてに既に付いていれば、逆にそれらから外す。
+    pub fn toggle_digit_notes(&mut self, num: Number) {
+        let board = self.board();
+        let squares: Vec<_> = board
+            .iter()
+            .filter(|&(sq, cell)| cell.is_none() && board.can_put(sq, num))
+            .map(|(sq, _)| sq)
+            .collect();
+        self.toggle_notes(&squares, num);
+    }
+
+    /// `squares` のうち空マスすべてにメモ `num` を付ける。すべてに既に付いていれば外す。
+    pub fn toggle_notes(&mut self, squares: &[Square], num: Number) {
+        let squares: Vec<_> = squares
+            .iter()
+            .copied()
+            .filter(|&sq| self.board()[sq].is_none())
+            .collect();
+        let on = !squares.iter().all(|&sq| self.has_note(sq, num));
+        for sq in squares {
+            self.set_note(sq, num, on);
+        }
+    }
+
+    /// 操作 `mv` を行う。`Game` を書き換える操作はすべてこれで表せる。
+    ///
+    /// 問題数字と確定印の付いたマスは書き換えられない。メモは空マスにしか付けられない。
+    pub fn apply_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        let sq = mv.square();
+        if self.is_given(sq) {
+            return Err(MoveError::Given { sq });
+        }
+        if self.is_confirmed(sq) {
+            return Err(MoveError::Confirmed { sq });
+        }
+        match mv {
+            Move::Place { sq, num } => {
+                if !self.put(sq, num) {
+                    return Err(MoveError::Rejected { sq });
+                }
+            }
+            // `Sudoku` には数字を消す API がないので、そのマスだけ空けた盤面から作り直す。
+            Move::Clear { sq } => {
+                let mut board = self.board().clone();
+                board[sq] = None;
+                self.sudoku = Sudoku::new(board);
+            }
+            Move::Note { sq, num } => {
+                if self.board()[sq].is_some() {
+                    return Err(MoveError::Rejected { sq });
+                }
+                self.set_note(sq, num, !self.has_note(sq, num));
+            }
+            Move::Strike { sq, num } => {
+                if !self.toggle_struck(sq, num) {
+                    return Err(MoveError::Rejected { sq });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `Move::Place` で `sq` に `num` を置き、それで新たに何がそろったかを返す。
+    ///
+    /// 置く前からそろっていたユニットや数字は含めない(仮置きの数字を書き換えた場合など)。
+    /// 置けなければ `apply_move` と同じエラーを返し、盤面は変わらない。
+    pub fn place_and_report(&mut self, sq: Square, num: Number) -> Result<PlaceOutcome, MoveError> {
+        let before = self.board().clone();
+        self.apply_move(Move::Place { sq, num })?;
+
+        let board = self.board();
+        let (row, col, block) = (sq.row(), sq.col(), sq.block());
+        let count = |board: &Board| board.iter().filter(|&(_, n)| n == Some(num)).count();
+
+        Ok(PlaceOutcome {
+            solved: self.is_solved(),
+            completed_row: (board.is_row_complete(row) && !before.is_row_complete(row))
+                .then_some(row),
+            completed_col: (board.is_col_complete(col) && !before.is_col_complete(col))
+                .then_some(col),
+            completed_block: (board.is_block_complete(block) && !before.is_block_complete(block))
+                .then_some(block),
+            number_completed: count(board) == 9 && count(&before) != 9,
+        })
+    }
+
+    /// `sq` に `num` を置くのが解と一致するか。盤面は変更しない。解がわからなければ `None`。
+    pub fn is_correct_move(&self, sq: Square, num: Number) -> Option<bool> {
+        let solution = self.solution.as_ref()?;
+        Some(!self.is_given(sq) && solution[sq] == Some(num))
+    }
+
+    /// 入力も含めた現在の盤面から、まだ最後まで埋められるか(`SudokuExt::is_still_solvable`)。
+    pub fn is_still_solvable(&self) -> bool {
+        self.sudoku.is_still_solvable()
+    }
+
+    /// 入力も含めた現在の盤面の 81 文字(`SudokuExt::board_string`)。
+    pub fn board_string(&self) -> String {
+        self.sudoku.board_string()
+    }
+
+    /// 盤面が埋まっていて矛盾がなければ `true`(`SudokuExt::is_complete_and_valid`)。
+    pub fn is_solved(&self) -> bool {
+        self.sudoku.is_complete_and_valid()
+    }
+
+    /// 保存用の文字列にする。
+    ///
+    /// 1 行目は問題数字だけの盤面、2 行目は入力も含めた現在の盤面で、どちらも `BoardExt::to_line`
+    /// の形式。3 行目は `Square::all()` 順の 81 文字の確定印(確定印は `#`、それ以外は `.`)、
+    /// 4 行目はメモ、5 行目は取り消し線で、マスごとの数字を `Square::all()` 順に空白区切りで
+    /// 並べる(`137` のように。なければ `.`)。3 行目以降は、それ以降がすべて空なら省く。
+    /// 解は保存されない。`from_save_string` で読み戻せる。
+    pub fn to_save_string(&self) -> String {
+        let confirmed: String = Square::all()
+            .into_iter()
+            .map(|sq| if self.is_confirmed(sq) { '#' } else { '.' })
+            .collect();
+        let mut lines = vec![
+            self.givens.to_line(),
+            self.board().to_line(),
+            confirmed,
+            format_number_sets(&self.notes),
+            format_number_sets(&self.struck),
+        ];
+        let is_empty = [
+            !self.confirmed.contains(&true),
+            self.notes.iter().all(|set| set.is_empty()),
+            self.struck.iter().all(|set| set.is_empty()),
+        ];
+        // 後ろから、空の行を省く。
+        for empty in is_empty.into_iter().rev() {
+            if !empty {
+                break;
+            }
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// `to_save_string` の形式を読み込む。盤面の行は `BoardExt::parse_flexible` でパースする。
+    ///
+    /// 2 行目は 1 行目の問題数字をすべて含み、そのうえで入力が置ける盤面でなければならない。
+    /// 確定印は入力のあるマスにしか付けられない。メモと取り消し線は書かれたとおりに戻す。
+    pub fn from_save_string(s: &str) -> Result<Self, ParseGameError> {
+        let lines: Vec<_> = s.lines().filter(|line| !line.trim().is_empty()).collect();
+        if !(2..=5).contains(&lines.len()) {
+            return Err(ParseGameError::LineCount { count: lines.len() });
+        }
+        let (givens, state) = (lines[0], lines[1]);
+        let confirmed = lines.get(2).copied();
+        let givens = Board::parse_flexible(givens)?;
+        let state = Board::parse_flexible(state)?;
+
+        let mut game = Self::new(Sudoku::try_new(givens)?);
+        for (sq, cell) in state.iter() {
+            match (game.givens[sq], cell) {
+                (Some(given), Some(num)) if given == num => {}
+                (Some(_), _) => return Err(ParseGameError::GivenMismatch { sq }),
+                (None, Some(num)) => {
+                    if !game.put(sq, num) {
+                        return Err(ParseGameError::InvalidEntry { sq });
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        if let Some(confirmed) = confirmed {
+            let marks: Vec<_> = confirmed.chars().filter(|ch| !ch.is_whitespace()).collect();
+            if marks.len() != 81 || marks.iter().any(|&ch| ch != '#' && ch != '.') {
+                return Err(ParseGameError::ConfirmedLine);
+            }
+            for (sq, mark) in Square::all().into_iter().zip(marks) {
+                if mark == '#' && !game.toggle_confirmed(sq) {
+                    return Err(ParseGameError::InvalidEntry { sq });
+                }
+            }
+        }
+        if let Some(notes) = lines.get(3) {
+            game.notes = parse_number_sets(notes).ok_or(ParseGameError::NotesLine { line: 4 })?;
+        }
+        if let Some(struck) = lines.get(4) {
+            game.struck = parse_number_sets(struck).ok_or(ParseGameError::NotesLine { line: 5 })?;
+        }
+
+        Ok(game)
+    }
+
+    /// 編集用: マス `sq` の問題数字を直接設定する(`None` なら消す)。プレイ中の入力には `put` を使う。
+    ///
+    /// 他の問題数字と重複する場合は何もせず `false` を返す。入力の数字とは比べない。
+    /// 設定できたら、`sq` の入力・メモ・取り消し線・確定印と、新しい問題数字と重複する入力は消える。
+    /// 問題が変わるので、解と難易度は忘れる。
+    pub fn set_given(&mut self, sq: Square, num: Option<Number>) -> bool {
+        let mut givens = self.givens.clone();
+        givens[sq] = None;
+        if num.is_some_and(|num| !givens.can_put(sq, num)) {
+            return false;
+        }
+        givens[sq] = num;
+
+        let entries: Vec<_> = self
+            .board()
+            .iter()
+            .filter(|&(s, _)| s != sq && !self.is_given(s))
+            .filter_map(|(s, cell)| cell.map(|n| (s, n)))
+            .collect();
+        self.sudoku = Sudoku::new(givens.clone());
+        self.givens = givens;
+        for (s, n) in entries {
+            if !self.sudoku.put(s, n) {
+                self.confirmed[s.index()] = false;
+            }
+        }
+
+        self.notes[sq.index()] = NumberSet::new();
+        self.struck[sq.index()] = NumberSet::new();
+        self.confirmed[sq.index()] = false;
+        self.solution = None;
+        self.difficulty = OnceCell::new();
+
+        true
+    }
+
+    /// 問題数字以外の数字とメモ、取り消し線、確定印をすべて消す。
+    pub fn clear_entries(&mut self) {
+        self.sudoku = Sudoku::new(self.givens.clone());
+        self.notes = [NumberSet::new(); 81];
+        self.struck = [NumberSet::new(); 81];
+        self.confirmed = [false; 81];
+    }
+}
+
+pub trait SudokuExt: Sized {
+    /// `board` の数字に行/列/ブロック内の重複がないか確かめてから `Sudoku::new` する。
+    ///
+    /// `Sudoku::new` 自体は盤面を検査しないので、任意の盤面(貼り付け、保存データ、編集)から作るとき
+    /// はこちらを使う。重複があれば、重複している数字のうち `Square::all()` 順で最初のマスを返す。
+    fn try_new(board: Board) -> Result<Self, ConflictError>;
+
+    /// 現在の盤面を解き、解とソルバーの推測回数を返す(`solver::solve_with_effort`)。
+    ///
+    /// 推測回数が多いほど難しいという、`techniques::rate` より軽い目安。
+    fn solve_with_effort(&self) -> (Option<Board>, u64);
+
+    /// `sq` に `num` を置いたら重複するマス(`BoardExt::conflicts`)。盤面は変更しない。
+    fn conflicts_for_move(&self, sq: Square, num: Number) -> Vec<Square>;
+
+    /// 入力も含めた現在の盤面から、まだ最後まで埋められるか。
+    ///
+    /// 重複がなくても、入力の組み合わせによってはもう解けないことがある。ソルバーを走らせるので重い。
+    fn is_still_solvable(&self) -> bool;
+
+    /// 盤面が埋まっていて、行/列/ブロックに重複がなければ `true`。
+    ///
+    /// 解と照合せずソルバーも走らせないので、解を持たない盤面でも毎手呼べるほど軽い。
+    /// 唯一解の問題なら、これが `true` になる盤面は解そのもの。
+    fn is_complete_and_valid(&self) -> bool;
+
+    /// 入力も含めた現在の盤面を `BoardExt::to_line` の 81 文字にする。ログや共有用。
+    fn board_string(&self) -> String;
+
+    /// 問題数字がちょうど `pattern` のマスを占める唯一解の問題を `(問題, 解)` として生成する。
+    ///
+    /// 解盤面を取り替えながら最大 `PATTERN_ATTEMPTS` 回試す。
+    fn generate_with_pattern(pattern: &[Square]) -> Result<(Self, Self), GeneratePatternError>;
+
+    /// 日付 `date` (例: `20261014`) に対応する「今日の問題」を `(問題, 解)` として生成する。
+    ///
+    /// 同じ日付からは常に同じ問題が得られる。
+    fn daily(date: impl Into<u64>) -> (Self, Self);
+
+    /// ヒント数 `clues` を目安に、推測なしにテクニックだけで解け、かつ難易度が `max_difficulty`
+    /// 以下の問題を生成する。
+    ///
+    /// 内部ではランダムなシードで `generate::generate_logical` を呼ぶ。候補ごとにテクニックソルバーが
+    /// 走るので `generate_unique` よりずっと遅い。規定回数で作れなければ `None`。
+    fn generate_logical(clues: usize, max_difficulty: Difficulty) -> Option<(Self, Self)>;
+
+    /// 次に使うべきテクニックがちょうど `technique` の問題を `(問題, 解)` として生成する。
+    ///
+    /// 内部ではランダムなシードで `generate::generate_technique_drill` を呼ぶ。作れなければ `None`。
+    fn generate_drill(technique: Technique) -> Option<(Self, Self)>;
+
+    /// ヒント数 `clues` を目標に唯一解の問題を生成し、問題の情報とともに返す。
+    ///
+    /// 内部ではランダムなシードで `generate::generate_seeded_with_meta` を呼ぶ。
+    /// 情報のシードを `generate::generate_seeded` に渡せば同じ問題を作り直せる。
+    fn generate_unique_with_meta(clues: usize) -> (Self, Self, PuzzleMeta);
+
+    /// ヒント数 `clues` を目標に唯一解の問題を生成し、生成の統計とともに返す。
+    ///
+    /// 内部ではランダムなシードで `generate::generate_seeded_with_stats` を呼ぶ。
+    fn generate_unique_with_stats(clues: usize) -> (Self, Self, GenerationStats);
+
+    /// ヒント数がちょうど `clues` の唯一解の問題を生成する。
+    ///
+    /// `clues` が 17 未満ならその場でエラー。ランダムなシードで最大 `GENERATE_ATTEMPTS` 回試し、
+    /// いずれも `clues` まで減らせなければ `GenerateError::Exhausted`。
+    /// 成功すれば `generate_unique_with_meta` と同じく問題の情報も返す。
+    fn try_generate_unique(clues: usize) -> Result<(Self, Self, PuzzleMeta), GenerateError>;
+}
+
+pub const PATTERN_ATTEMPTS: usize = 100;
+
+pub const DAILY_CLUES: usize = 35;
+
+pub const GENERATE_ATTEMPTS: usize = 20;
+
+impl SudokuExt for Sudoku {
+    fn try_new(board: Board) -> Result<Self, ConflictError> {
+        let conflict = board
+            .iter()
+            .find(|&(sq, cell)| cell.is_some_and(|num| !board.can_put(sq, num)));
+        if let Some((sq, _)) = conflict {
+            return Err(ConflictError { sq });
+        }
+
+        Ok(Sudoku::new(board))
+    }
+
+    fn solve_with_effort(&self) -> (Option<Board>, u64) {
+        solver::solve_with_effort(self.board())
+    }
+
+    fn conflicts_for_move(&self, sq: Square, num: Number) -> Vec<Square> {
+        self.board().conflicts(sq, num)
+    }
+
+    fn is_still_solvable(&self) -> bool {
+        solver::solve(self.board()).is_some()
+    }
+
+    fn is_complete_and_valid(&self) -> bool {
+        let board = self.board();
+        board.is_full() && board.is_valid()
+    }
+
+    fn board_string(&self) -> String {
+        self.board().to_line()
+    }
+
+    fn generate_with_pattern(pattern: &[Square]) -> Result<(Self, Self), GeneratePatternError> {
+        let squares: Vec<_> = Square::all()
+            .into_iter()
+            .filter(|sq| pattern.contains(sq))
+            .collect();
+        // 17 個未満のヒントで唯一解になる問題は存在しない。
+        if squares.len() < 17 {
+            return Err(GeneratePatternError::TooFewSquares {
+                count: squares.len(),
+            });
+        }
+
+        let (puzzle, solution) =
+            generate::generate_with_pattern_seeded(rand::random(), &squares, PATTERN_ATTEMPTS)
+                .ok_or(GeneratePatternError::NotUnique)?;
+        Ok((Sudoku::new(puzzle), Sudoku::new(solution)))
+    }
+
+    fn daily(date: impl Into<u64>) -> (Self, Self) {
+        let (puzzle, solution) = generate::generate_seeded(date.into(), DAILY_CLUES);
+        (Sudoku::new(puzzle), Sudoku::new(solution))
+    }
+
+    fn generate_logical(clues: usize, max_difficulty: Difficulty) -> Option<(Self, Self)> {
+        let (puzzle, solution, _) =
+            generate::generate_logical(rand::random(), clues, max_difficulty)?;
+        Some((Sudoku::new(puzzle), Sudoku::new(solution)))
+    }
+
+    fn generate_drill(technique: Technique) -> Option<(Self, Self)> {
+        let (puzzle, solution) = generate::generate_technique_drill(rand::random(), technique)?;
+        Some((Sudoku::new(puzzle), Sudoku::new(solution)))
+    }
+
+    fn generate_unique_with_meta(clues: usize) -> (Self, Self, PuzzleMeta) {
+        let (puzzle, solution, meta) = generate::generate_seeded_with_meta(rand::random(), clues);
+        (Sudoku::new(puzzle), Sudoku::new(solution), meta)
+    }
+
+    fn generate_unique_with_stats(clues: usize) -> (Self, Self, GenerationStats) {
+        let (puzzle, solution, stats) = generate::generate_seeded_with_stats(rand::random(), clues);
+        (Sudoku::new(puzzle), Sudoku::new(solution), stats)
+    }
+
+    fn try_generate_unique(clues: usize) -> Result<(Self, Self, PuzzleMeta), GenerateError> {
+        if clues < 17 {
+            return Err(GenerateError::TooFewClues { clues });
+        }
+
+        for _ in 0..GENERATE_ATTEMPTS {
+            let seed = rand::random();
+            let (puzzle, solution, stats) = generate::generate_seeded_with_stats(seed, clues);
+            if stats.clues == clues {
+                let meta = PuzzleMeta::new(seed, &puzzle);
+                return Ok((Sudoku::new(puzzle), Sudoku::new(solution), meta));
+            }
+        }
+
+        Err(GenerateError::Exhausted)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseGameError {
+    LineCount {
+        count: usize,
+    },
+    Board(ParseBoardError),
+    /// 2 行目のマス `sq` が問題数字と食い違っている。
+    GivenMismatch {
+        sq: Square,
+    },
+    /// 2 行目のマス `sq` の入力が置けなかった(または確定印を付けられなかった)。
+    InvalidEntry {
+        sq: Square,
+    },
+    /// 3 行目が確定印の行として読めない。
+    ConfirmedLine,
+    /// `line` 行目(4 行目ならメモ、5 行目なら取り消し線)がマスごとの数字の並びとして読めない。
+    NotesLine {
+        line: usize,
+    },
+    /// 1 行目の問題数字どうしが重複している。
+    Conflict(ConflictError),
+}
+
+impl From<ParseBoardError> for ParseGameError {
+    fn from(e: ParseBoardError) -> Self {
+        Self::Board(e)
+    }
+}
+
+impl From<ConflictError> for ParseGameError {
+    fn from(e: ConflictError) -> Self {
+        Self::Conflict(e)
+    }
+}
+
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::LineCount { count } => write!(f, "expected 2 to 5 lines, got {count}"),
+            Self::Board(e) => e.fmt(f),
+            Self::GivenMismatch { sq } => write!(f, "{} does not match the given", sq.label()),
+            Self::InvalidEntry { sq } => write!(f, "{} is not a valid entry", sq.label()),
+            Self::ConfirmedLine => write!(f, "invalid confirmation line"),
+            Self::NotesLine { line } => write!(f, "invalid notes on line {line}"),
+            Self::Conflict(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseGameError {}
+
+/// マスごとの数字の集合を `to_save_string` の 4・5 行目の形式にする。
+fn format_number_sets(sets: &[NumberSet; 81]) -> String {
+    let tokens: Vec<String> = sets
+        .iter()
+        .map(|set| {
+            if set.is_empty() {
+                ".".to_owned()
+            } else {
+                set.iter().map(|num| num.get().to_string()).collect()
+            }
+        })
+        .collect();
+    tokens.join(" ")
+}
+
+/// `format_number_sets` の逆。81 マス分ちょうどでなければ `None`。
+fn parse_number_sets(line: &str) -> Option<[NumberSet; 81]> {
+    let tokens: Vec<_> = line.split_whitespace().collect();
+    if tokens.len() != 81 {
+        return None;
+    }
+    let mut sets = [NumberSet::new(); 81];
+    for (set, token) in sets.iter_mut().zip(tokens) {
+        if token == "." {
+            continue;
+        }
+        for ch in token.chars() {
+            let n = ch.to_digit(10).filter(|n| (1..=9).contains(n))?;
+            set.insert(Number::all()[n as usize - 1]);
+        }
+    }
+    Some(sets)
+}
+
+/// プレイヤーの 1 回の操作。`Game::apply_move` で適用する。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Move {
+    /// `sq` に `num` を置く。
+    Place { sq: Square, num: Number },
+    /// `sq` の入力を消す。
+    Clear { sq: Square },
+    /// `sq` のメモ `num` を付け外しする。
+    Note { sq: Square, num: Number },
+    /// `sq` の候補 `num` の取り消し線を付け外しする。
+    Strike { sq: Square, num: Number },
+}
+
+impl Move {
+    /// 操作の対象のマス。
+    pub fn square(self) -> Square {
+        match self {
+            Self::Place { sq, .. }
+            | Self::Clear { sq }
+            | Self::Note { sq, .. }
+            | Self::Strike { sq, .. } => sq,
+        }
+    }
+}
+
+/// `Game::place_and_report` で数字を置いた結果。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PlaceOutcome {
+    /// 盤面が完成した(`Game::is_solved`)。
+    pub solved: bool,
+    /// 置いたマスを含む行のうち、この手で重複なく埋まったもの。
+    pub completed_row: Option<Row>,
+    /// 置いたマスを含む列のうち、この手で重複なく埋まったもの。
+    pub completed_col: Option<Col>,
+    /// 置いたマスを含むブロックのうち、この手で重複なく埋まったもの。
+    pub completed_block: Option<Block>,
+    /// 置いた数字がこの手で盤面に 9 個そろった。
+    pub number_completed: bool,
+}
+
+impl PlaceOutcome {
+    /// そろった行/列/ブロックのマスを、重複なく `Square::all()` 順に返す。どれもそろわなければ空。
+    pub fn completed_squares(&self) -> Vec<Square> {
+        Square::all()
+            .into_iter()
+            .filter(|sq| {
+                self.completed_row == Some(sq.row())
+                    || self.completed_col == Some(sq.col())
+                    || self.completed_block == Some(sq.block())
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MoveError {
+    /// `sq` は問題数字のマス。
+    Given { sq: Square },
+    /// `sq` には確定印が付いている。
+    Confirmed { sq: Square },
+    /// `sq` にはその操作を行えない(数字を置けない、入力のあるマスにメモや取り消し線を付けるなど)。
+    Rejected { sq: Square },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Given { sq } => write!(f, "{} is a given", sq.label()),
+            Self::Confirmed { sq } => write!(f, "{} is confirmed", sq.label()),
+            Self::Rejected { sq } => write!(f, "the move is not allowed at {}", sq.label()),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// 盤面のマス `sq` の数字が、同じ行/列/ブロックの数字と重複している。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConflictError {
+    pub sq: Square,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} conflicts with another digit", self.sq.label())
+    }
+}
+
+impl std::error::Error for ConflictError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GenerateError {
+    /// 17 個未満のヒントで唯一解になる問題は存在しない。
+    TooFewClues { clues: usize },
+    /// 規定回数試しても目標のヒント数に届かなかった。
+    Exhausted,
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooFewClues { clues } => {
+                write!(f, "{clues} clues is too few (at least 17 required)")
+            }
+            Self::Exhausted => write!(
+                f,
+                "could not reach the requested clue count in {GENERATE_ATTEMPTS} attempts"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GeneratePatternError {
+    TooFewSquares { count: usize },
+    NotUnique,
+}
+
+impl fmt::Display for GeneratePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooFewSquares { count } => {
+                write!(f, "pattern has only {count} squares (at least 17 required)")
+            }
+            Self::NotUnique => write!(
+                f,
+                "no unique puzzle found for the pattern in {PATTERN_ATTEMPTS} attempts"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeneratePatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::row_squares;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    fn sample() -> Game {
+        Game::new(Sudoku::new(Board::from_line(PUZZLE).unwrap()))
+    }
+
+    fn sq(index: usize) -> Square {
+        Square::all()[index]
+    }
+
+    fn num(n: u8) -> Number {
+        Number::all()[usize::from(n) - 1]
+    }
+
+    /// 入力・確定印・メモ・取り消し線の入ったゲーム。
+    fn played() -> Game {
+        let mut game = sample();
+        assert!(game.put(sq(2), num(4)));
+        assert!(game.put(sq(3), num(6)));
+        assert!(game.toggle_confirmed(sq(3)));
+        // 間違っているが重複しない入力。
+        assert!(game.put(sq(5), num(2)));
+        game.set_note(sq(6), num(1), true);
+        game.set_note(sq(6), num(9), true);
+        assert!(game.toggle_struck(sq(7), num(2)));
+        game
+    }
+
+    #[test]
+    fn save_string_round_trip() {
+        for game in [sample(), played()] {
+            let loaded = Game::from_save_string(&game.to_save_string()).unwrap();
+            assert_eq!(loaded, game);
+            assert_eq!(loaded.to_save_string(), game.to_save_string());
+        }
+    }
+
+    #[test]
+    fn save_string_round_trip_keeps_each_part() {
+        let game = played();
+        let loaded = Game::from_save_string(&game.to_save_string()).unwrap();
+        assert!(!loaded.is_given(sq(2)));
+        assert_eq!(loaded.board()[sq(5)], Some(num(2)));
+        assert!(loaded.is_confirmed(sq(3)));
+        assert!(!loaded.is_confirmed(sq(2)));
+        assert_eq!(
+            loaded.notes(sq(6)).iter().collect::<Vec<_>>(),
+            [num(1), num(9)]
+        );
+        assert!(loaded.struck(sq(7)).contains(num(2)));
+        assert!(loaded.solution().is_none());
+    }
+
+    #[test]
+    fn save_string_omits_trailing_empty_lines() {
+        assert_eq!(sample().to_save_string().lines().count(), 2);
+
+        let mut game = sample();
+        game.set_note(sq(2), num(4), true);
+        let s = game.to_save_string();
+        assert_eq!(s.lines().count(), 4);
+        assert_eq!(Game::from_save_string(&s).unwrap(), game);
+    }
+
+    #[test]
+    fn save_string_equality_ignores_solution() {
+        let game = sample();
+        let mut solved = sample();
+        solved.set_solution(solver::solve(game.givens()).unwrap());
+        assert_eq!(game, solved);
+    }
+
+    #[test]
+    fn save_string_inequality() {
+        let mut other = played();
+        other.set_note(sq(6), num(9), false);
+        assert_ne!(other, played());
+
+        let mut other = played();
+        assert!(other.toggle_struck(sq(7), num(2)));
+        assert_ne!(other, played());
+
+        let mut other = played();
+        assert!(other.toggle_confirmed(sq(3)));
+        assert_ne!(other, played());
+    }
+
+    #[test]
+    fn from_save_string_errors() {
+        let s = played().to_save_string();
+        let lines: Vec<_> = s.lines().collect();
+
+        assert_eq!(
+            Game::from_save_string(lines[0]),
+            Err(ParseGameError::LineCount { count: 1 })
+        );
+        let too_many = format!("{s}\n{}", lines[4]);
+        assert_eq!(
+            Game::from_save_string(&too_many),
+            Err(ParseGameError::LineCount { count: 6 })
+        );
+
+        let bad_notes = [lines[0], lines[1], lines[2], ". 1"].join("\n");
+        assert_eq!(
+            Game::from_save_string(&bad_notes),
+            Err(ParseGameError::NotesLine { line: 4 })
+        );
+        let bad_struck = [
+            lines[0],
+            lines[1],
+            lines[2],
+            lines[3],
+            &lines[3].replace('9', "0"),
+        ];
+        assert_eq!(
+            Game::from_save_string(&bad_struck.join("\n")),
+            Err(ParseGameError::NotesLine { line: 5 })
+        );
+
+        let bad_confirmed = [lines[0], lines[1], "#"].join("\n");
+        assert_eq!(
+            Game::from_save_string(&bad_confirmed),
+            Err(ParseGameError::ConfirmedLine)
+        );
+    }
+
+    #[test]
+    fn set_given_updates_givens_and_board() {
+        let mut game = sample();
+        assert!(game.set_given(sq(2), Some(num(4))));
+        assert!(game.is_given(sq(2)));
+        assert_eq!(game.givens()[sq(2)], Some(num(4)));
+        assert_eq!(game.board()[sq(2)], Some(num(4)));
+
+        assert!(game.set_given(sq(0), None));
+        assert!(!game.is_given(sq(0)));
+        assert_eq!(game.board()[sq(0)], None);
+    }
+
+    #[test]
+    fn set_given_rejects_conflict_with_givens() {
+        let mut game = sample();
+        assert!(!game.set_given(sq(2), Some(num(5))));
+        assert_eq!(game.givens().key(), sample().givens().key());
+        assert_eq!(game.board().key(), sample().board().key());
+    }
+
+    #[test]
+    fn set_given_keeps_entries_as_entries() {
+        let mut game = sample();
+        assert!(game.put(sq(2), num(4)));
+        assert!(game.set_given(sq(3), Some(num(6))));
+        assert!(!game.is_given(sq(2)));
+        assert_eq!(game.board()[sq(2)], Some(num(4)));
+        assert_eq!(game.givens()[sq(2)], None);
+    }
+
+    #[test]
+    fn set_given_ignores_and_drops_conflicting_entries() {
+        let mut game = sample();
+        game.set_solution(solver::solve(game.givens()).unwrap());
+        assert!(game.put(sq(2), num(4)));
+        assert!(game.toggle_confirmed(sq(2)));
+        assert!(game.set_given(sq(6), Some(num(4))));
+        assert!(game.is_given(sq(6)));
+        assert_eq!(game.board()[sq(2)], None);
+        assert!(!game.is_confirmed(sq(2)));
+        assert!(game.solution().is_none());
+    }
+
+    #[test]
+    fn generate_with_pattern_rejects_too_few_squares() {
+        let pattern: Vec<Square> = Square::all().into_iter().take(16).collect();
+        assert_eq!(
+            Sudoku::generate_with_pattern(&pattern).map(|_| ()),
+            Err(GeneratePatternError::TooFewSquares { count: 16 })
+        );
+    }
+
+    #[test]
+    fn generate_with_pattern_keeps_only_pattern_squares() {
+        let pattern: Vec<Square> = Square::all()
+            .into_iter()
+            .filter(|sq| (sq.row_index() + sq.col_index()) % 2 == 0)
+            .collect();
+        let (puzzle, solution) = Sudoku::generate_with_pattern(&pattern).unwrap();
+        for sq in Square::all() {
+            let expected = if pattern.contains(&sq) {
+                solution.board()[sq]
+            } else {
+                None
+            };
+            assert_eq!(puzzle.board()[sq], expected, "{sq:?}");
+        }
+        assert_eq!(solver::count_solutions(puzzle.board(), 2), 1);
+    }
+
+    #[test]
+    fn save_string_has_givens_then_state() {
+        let mut game = sample();
+        assert!(game.put(sq(2), num(4)));
+        let s = game.to_save_string();
+        let lines: Vec<_> = s.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], PUZZLE);
+        assert_eq!(lines[1], PUZZLE.replacen('.', "4", 1));
+    }
+
+    #[test]
+    fn save_string_keeps_given_entry_distinction() {
+        let mut game = sample();
+        assert!(game.put(sq(2), num(4)));
+        let loaded = Game::from_save_string(&game.to_save_string()).unwrap();
+        for sq in Square::all() {
+            assert_eq!(loaded.is_given(sq), game.is_given(sq), "{}", sq.label());
+        }
+        assert!(!loaded.is_given(sq(2)));
+        assert_eq!(loaded.givens().key(), sample().givens().key());
+    }
+
+    #[test]
+    fn from_save_string_rejects_given_mismatch() {
+        // 2 行目で問題数字 (R1C1 の 5) を消している。
+        let s = format!("{PUZZLE}\n.{}", &PUZZLE[1..]);
+        assert_eq!(
+            Game::from_save_string(&s),
+            Err(ParseGameError::GivenMismatch { sq: sq(0) })
+        );
+    }
+
+    #[test]
+    fn from_save_string_rejects_conflicting_entry() {
+        // R1C3 に、同じ行の問題数字と重複する 5 を入れている。
+        let s = format!("{PUZZLE}\n{}", PUZZLE.replacen('.', "5", 1));
+        assert_eq!(
+            Game::from_save_string(&s),
+            Err(ParseGameError::InvalidEntry { sq: sq(2) })
+        );
+    }
+
+    #[test]
+    fn clone_is_equal_and_keeps_solution() {
+        let mut game = played();
+        game.set_solution(solver::solve(game.givens()).unwrap());
+        let copy = game.clone();
+        assert_eq!(copy, game);
+        assert_eq!(
+            copy.solution().map(BoardExt::key),
+            game.solution().map(BoardExt::key)
+        );
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let game = played();
+        let mut copy = game.clone();
+        assert!(copy.put(sq(8), num(8)));
+        copy.set_note(sq(6), num(1), false);
+        assert!(copy.toggle_struck(sq(7), num(2)));
+        assert!(copy.toggle_confirmed(sq(2)));
+
+        assert_ne!(copy, game);
+        assert_eq!(game, played());
+        assert_eq!(game.board()[sq(8)], None);
+        assert!(game.has_note(sq(6), num(1)));
+
+        let mut original = played();
+        let copy = original.clone();
+        original.clear_entries();
+        assert_eq!(copy, played());
+    }
+
+    #[test]
+    fn conflicts_for_move_lists_peers_holding_number() {
+        let sudoku = Sudoku::new(Board::from_line(PUZZLE).unwrap());
+        // R1C3 に 5 を置くと、同じ行の R1C1 と重複する。
+        assert_eq!(sudoku.conflicts_for_move(sq(2), num(5)), [sq(0)]);
+        // 9 は同じブロックの R3C2 と重複する。
+        assert_eq!(sudoku.conflicts_for_move(sq(2), num(9)), [sq(19)]);
+        // 8 は同じ列(かつ同じブロック)の R3C3 と重複する。
+        assert_eq!(sudoku.conflicts_for_move(sq(2), num(8)), [sq(20)]);
+    }
+
+    #[test]
+    fn conflicts_for_move_is_empty_for_legal_move() {
+        let sudoku = Sudoku::new(Board::from_line(PUZZLE).unwrap());
+        assert!(sudoku.conflicts_for_move(sq(2), num(4)).is_empty());
+        assert!(sudoku.conflicts_for_move(sq(2), num(1)).is_empty());
+    }
+
+    #[test]
+    fn conflicts_for_move_does_not_change_board() {
+        let sudoku = Sudoku::new(Board::from_line(PUZZLE).unwrap());
+        let _ = sudoku.conflicts_for_move(sq(2), num(5));
+        assert_eq!(sudoku.board().to_line(), PUZZLE);
+    }
+
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    /// 解から `empty` のマスだけ空けた盤面を問題とするゲーム。
+    fn nearly_solved(empty: &[usize]) -> Game {
+        let mut board = Board::from_line(SOLUTION).unwrap();
+        for &i in empty {
+            board[sq(i)] = None;
+        }
+        Game::new(Sudoku::new(board))
+    }
+
+    #[test]
+    fn place_and_report_plain_placement() {
+        let mut game = sample();
+        assert_eq!(
+            game.place_and_report(sq(2), num(4)),
+            Ok(PlaceOutcome::default())
+        );
+        assert_eq!(game.board()[sq(2)], Some(num(4)));
+    }
+
+    #[test]
+    fn place_and_report_row_completed() {
+        // R2C1 が空いているので、列 1 とブロック 1 はそろわない。R4C2 の 5 も空いている。
+        let mut game = nearly_solved(&[0, 9, 28]);
+        let outcome = game.place_and_report(sq(0), num(5)).unwrap();
+        assert_eq!(
+            outcome,
+            PlaceOutcome {
+                solved: false,
+                completed_row: Some(Row::all()[0]),
+                completed_col: None,
+                completed_block: None,
+                number_completed: false,
+            }
+        );
+        assert_eq!(outcome.completed_squares(), row_squares(Row::all()[0]));
+    }
+
+    #[test]
+    fn place_and_report_number_completed() {
+        // R1C2 と R2C1 が空いているので、どのユニットもそろわないが、5 は 9 個そろう。
+        let mut game = nearly_solved(&[0, 1, 9]);
+        let outcome = game.place_and_report(sq(0), num(5)).unwrap();
+        assert_eq!(
+            outcome,
+            PlaceOutcome {
+                solved: false,
+                completed_row: None,
+                completed_col: None,
+                completed_block: None,
+                number_completed: true,
+            }
+        );
+        assert!(outcome.completed_squares().is_empty());
+    }
+
+    #[test]
+    fn place_and_report_solved() {
+        let mut game = nearly_solved(&[0]);
+        let outcome = game.place_and_report(sq(0), num(5)).unwrap();
+        assert_eq!(
+            outcome,
+            PlaceOutcome {
+                solved: true,
+                completed_row: Some(Row::all()[0]),
+                completed_col: Some(Col::all()[0]),
+                completed_block: Some(Block::all()[0]),
+                number_completed: true,
+            }
+        );
+        assert!(game.is_solved());
+
+        // 行 1・列 1・ブロック 1 の和集合を、重複なく `Square::all()` 順に。
+        let expected: Vec<_> = Square::all()
+            .into_iter()
+            .filter(|s| s.row_index() == 0 || s.col_index() == 0 || s.block_index() == 0)
+            .collect();
+        assert_eq!(outcome.completed_squares(), expected);
+        assert_eq!(expected.len(), 21);
+    }
+
+    #[test]
+    fn place_and_report_skips_units_complete_before() {
+        // そろった後に同じ数字を置き直しても、新たにそろったものはない。
+        let mut game = nearly_solved(&[0]);
+        game.place_and_report(sq(0), num(5)).unwrap();
+        let outcome = game.place_and_report(sq(0), num(5)).unwrap();
+        assert_eq!(
+            outcome,
+            PlaceOutcome {
+                solved: true,
+                ..PlaceOutcome::default()
+            }
+        );
+    }
+
+    #[test]
+    fn place_and_report_errors_leave_game_unchanged() {
+        let mut game = sample();
+        assert_eq!(
+            game.place_and_report(sq(0), num(1)),
+            Err(MoveError::Given { sq: sq(0) })
+        );
+        assert_eq!(
+            game.place_and_report(sq(2), num(5)),
+            Err(MoveError::Rejected { sq: sq(2) })
+        );
+        assert_eq!(game, sample());
+
+        assert!(game.place_and_report(sq(2), num(4)).is_ok());
+        assert!(game.toggle_confirmed(sq(2)));
+        let before = game.clone();
+        assert_eq!(
+            game.place_and_report(sq(2), num(1)),
+            Err(MoveError::Confirmed { sq: sq(2) })
+        );
+        assert_eq!(game, before);
+    }
+
+    #[test]
+    fn try_generate_unique_rejects_too_few_clues() {
+        for clues in [0, 16] {
+            assert_eq!(
+                Sudoku::try_generate_unique(clues).map(|_| ()),
+                Err(GenerateError::TooFewClues { clues })
+            );
+        }
+    }
+
+    #[test]
+    fn try_generate_unique_reaches_requested_clues() {
+        let (puzzle, solution, meta) = Sudoku::try_generate_unique(35).unwrap();
+        let clues = puzzle.board().iter().filter(|&(_, n)| n.is_some()).count();
+        assert_eq!(clues, 35);
+        assert_eq!(meta.clue_count, 35);
+        assert_eq!(solver::count_solutions(puzzle.board(), 2), 1);
+        let solved = solver::solve(puzzle.board()).map(|b| b.key());
+        assert_eq!(solved, Some(solution.board().key()));
+    }
+}
@@ -0,0 +1,523 @@
+//! シードから再現可能な問題生成。
+//!
+//! 解盤面は決定的ソルバーで得た基準解に、数独の解であることを保つ変換
+//! (数字の付け替え、バンド内の行入れ替え、バンド入れ替え、列/スタックの同様の入れ替え、転置)
+//! を乱数で施して作る。その後マスをランダムな順に消していき、解が一意である限り消し続ける。
+//! 乱数は ChaCha8 なので、同じシードからはプラットフォームによらず同じ問題が得られる。
+
+use std::time::Duration;
+// wasm32-unknown-unknown では `std::time::Instant::now` が panic するので、web では instant を使う。
+#[cfg(not(feature = "web"))]
+use std::time::Instant;
+
+#[cfg(feature = "web")]
+use instant::Instant;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sudoku::*;
+
+use crate::board::BoardExt;
+use crate::solver;
+use crate::square::SquareExt;
+use crate::symmetry::{Symmetry, SymmetrySet};
+use crate::techniques::{self, Difficulty, Technique};
+
+/// シード `seed` から、ヒント数 `clues` を目標に唯一解の問題を `(問題, 解)` として生成する。
+///
+/// 消せるマスが尽きた場合、ヒント数は `clues` より多くなることがある。
+pub fn generate_seeded(seed: u64, clues: usize) -> (Board, Board) {
+    let (puzzle, solution, _) = generate_seeded_with_stats(seed, clues);
+    (puzzle, solution)
+}
+
+/// `generate_seeded` と同じだが、生成にかかった手間も返す。
+pub fn generate_seeded_with_stats(seed: u64, clues: usize) -> (Board, Board, GenerationStats) {
+    let start = Instant::now();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let solution = random_solution(&mut rng);
+    let (puzzle, attempts) = dig(&solution, clues, &mut rng);
+    debug_assert_eq!(
+        solver::count_solutions(&puzzle, 2),
+        1,
+        "generated puzzle must have a unique solution (seed {seed})"
+    );
+
+    let stats = GenerationStats {
+        attempts,
+        clues: count_clues(&puzzle),
+        difficulty: None,
+        elapsed: start.elapsed(),
+    };
+
+    (puzzle, solution, stats)
+}
+
+/// `generate_seeded` と同じだが、問題の情報 `PuzzleMeta` も返す。
+///
+/// 難易度を求めるためにテクニックソルバーを 1 回走らせる。
+pub fn generate_seeded_with_meta(seed: u64, clues: usize) -> (Board, Board, PuzzleMeta) {
+    let (puzzle, solution) = generate_seeded(seed, clues);
+    let meta = PuzzleMeta::new(seed, &puzzle);
+    (puzzle, solution, meta)
+}
+
+/// 生成した問題のラベル付け・保存・再現に必要な情報。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PuzzleMeta {
+    /// 生成に使ったシード。同じ生成関数に同じシードを渡せば同じ問題が得られる。
+    pub seed: u64,
+    /// 問題数字の個数。
+    pub clue_count: usize,
+    /// 難易度(`techniques::rate`)。
+    pub difficulty: Difficulty,
+    /// 問題数字の配置が持つ対称性(`BoardExt::symmetry`)。対称性を指定せずに生成しても、
+    /// たまたま持っていれば含まれる。
+    pub symmetry: SymmetrySet,
+}
+
+impl PuzzleMeta {
+    /// シード `seed` から生成した問題 `puzzle` の情報を求める。
+    pub fn new(seed: u64, puzzle: &Board) -> Self {
+        Self {
+            seed,
+            clue_count: count_clues(puzzle),
+            difficulty: techniques::rate(puzzle),
+            symmetry: puzzle.symmetry(),
+        }
+    }
+}
+
+/// `generate_with` に渡す条件。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GenerateOptions {
+    /// 目標のヒント数。ほかの条件を満たすためなら前後してよい(目安)。
+    pub clues: usize,
+    /// 難易度(`techniques::rate`)をちょうどこれにする。
+    pub difficulty: Option<Difficulty>,
+    /// 問題数字の配置にこの対称性を持たせる。
+    pub symmetry: Option<Symmetry>,
+    /// テクニックだけで解ける(`Difficulty::Expert` でない)問題にする。
+    pub logical_only: bool,
+    /// どの空マスも候補(`BoardExt::candidates`)がこの個数以下の問題にする。初心者向けの目安。
+    pub max_candidates: Option<usize>,
+}
+
+/// `generate_with` が試す解盤面の数。
+pub const GENERATE_WITH_ATTEMPTS: usize = 20;
+
+/// シード `seed` から、条件 `options` を満たす唯一解の問題を `(問題, 解, 統計)` として生成する。
+///
+/// `symmetry`・`difficulty`・`logical_only`・`max_candidates` は必ず満たし、`clues` は目安として扱う。
+/// ヒント数 `clues` まで消した時点で難易度が目標より易しければさらに消し続け、
+/// 難しすぎれば消したマスを新しい順に戻していく。マスを消すと候補は増える一方なので、
+/// `max_candidates` を超えるマスは消さずに残す。統計の `clues` と `difficulty` で目標とのずれがわかる。
+///
+/// 解盤面を取り替えながら最大 `GENERATE_WITH_ATTEMPTS` 回試し、満たせなければ `None`。
+/// `difficulty == Some(Difficulty::Expert)` と `logical_only` のように両立しない条件でも `None`。
+pub fn generate_with(
+    seed: u64,
+    options: &GenerateOptions,
+) -> Option<(Board, Board, GenerationStats)> {
+    let start = Instant::now();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    // 満たすべき難易度の範囲。
+    let (min, max) = match (options.difficulty, options.logical_only) {
+        (Some(Difficulty::Expert), true) => return None,
+        (Some(d), _) => (d, d),
+        (None, true) => (Difficulty::Easy, Difficulty::Hard),
+        (None, false) => (Difficulty::Easy, Difficulty::Expert),
+    };
+    let rated = options.difficulty.is_some() || options.logical_only;
+
+    let mut attempts = 0;
+    for _ in 0..GENERATE_WITH_ATTEMPTS {
+        let solution = random_solution(&mut rng);
+        let mut orbits = symmetry_orbits(options.symmetry);
+        orbits.shuffle(&mut rng);
+
+        let mut board = solution.clone();
+        let mut removed: Vec<&[Square]> = vec![];
+        // 埋まりきった盤面は当然テクニックだけで解ける。
+        let mut difficulty = Difficulty::Easy;
+
+        // ヒント数が目標に届くか、(難易度の条件があれば)目標の難易度に届くまで消す。
+        for orbit in &orbits {
+            let settled = count_clues(&board) <= options.clues && (!rated || difficulty >= min);
+            if settled {
+                break;
+            }
+            attempts += 1;
+            for &sq in orbit {
+                board[sq] = None;
+            }
+            let capped = match options.max_candidates {
+                Some(max) => max_candidate_count(&board) <= max,
+                None => true,
+            };
+            if capped && solver::count_solutions(&board, 2) == 1 {
+                removed.push(orbit);
+                if rated {
+                    difficulty = techniques::rate(&board);
+                }
+            } else {
+                for &sq in orbit {
+                    board[sq] = solution[sq];
+                }
+            }
+        }
+
+        // 難しすぎれば、消したマスを新しい順に戻す。
+        while rated && difficulty > max {
+            let Some(orbit) = removed.pop() else {
+                break;
+            };
+            for &sq in orbit {
+                board[sq] = solution[sq];
+            }
+            difficulty = techniques::rate(&board);
+        }
+
+        if (min..=max).contains(&difficulty) {
+            debug_assert_eq!(solver::count_solutions(&board, 2), 1);
+            let stats = GenerationStats {
+                attempts,
+                clues: count_clues(&board),
+                difficulty: rated.then_some(difficulty),
+                elapsed: start.elapsed(),
+            };
+            return Some((board, solution, stats));
+        }
+    }
+
+    None
+}
+
+/// `generate_logical` が `generate_with` を呼ぶ回数。
+pub const LOGICAL_ATTEMPTS: usize = 10;
+
+/// シード `seed` から、推測なしにテクニックだけで解け、難易度が `max_difficulty` 以下の唯一解の問題を
+/// `(問題, 解, 統計)` として生成する。`clues` は `generate_with` と同じく目安。
+///
+/// `logical_only` を付けた `generate_with` を、シードを変えながら最大 `LOGICAL_ATTEMPTS` 回呼び、
+/// 難易度が上限を超えたものは捨てる。候補ごとにレーティングを繰り返すので、`generate_seeded` より
+/// ずっと遅い。どれも満たさなければ `None`。
+pub fn generate_logical(
+    seed: u64,
+    clues: usize,
+    max_difficulty: Difficulty,
+) -> Option<(Board, Board, GenerationStats)> {
+    let options = GenerateOptions {
+        clues,
+        difficulty: None,
+        symmetry: None,
+        logical_only: true,
+        max_candidates: None,
+    };
+    (0..LOGICAL_ATTEMPTS as u64)
+        .filter_map(|i| generate_with(seed.wrapping_add(i), &options))
+        .find(|(_, _, stats)| stats.difficulty.is_some_and(|d| d <= max_difficulty))
+}
+
+/// シード `seed` から、問題数字がちょうど `squares` のマスを占める唯一解の問題を `(問題, 解)`
+/// として生成する。
+///
+/// 解盤面を取り替えながら最大 `attempts` 回試し、どれでも解が一意にならなければ `None`。
+pub fn generate_with_pattern_seeded(
+    seed: u64,
+    squares: &[Square],
+    attempts: usize,
+) -> Option<(Board, Board)> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    for _ in 0..attempts {
+        let solution = random_solution(&mut rng);
+        let mut board = Board::empty();
+        for &sq in squares {
+            board[sq] = solution[sq];
+        }
+        if solver::count_solutions(&board, 2) == 1 {
+            return Some((board, solution));
+        }
+    }
+
+    None
+}
+
+/// `generate_technique_drill` が試す問題の数。
+pub const DRILL_ATTEMPTS: usize = 20;
+
+/// `generate_technique_drill` が元の問題に使うヒント数の目安。
+const DRILL_CLUES: usize = 24;
+
+/// シード `seed` から、次に使うべき最も易しいテクニック(`techniques::easiest_step`)がちょうど
+/// `technique` になる唯一解の盤面を `(問題, 解)` として生成する。テクニックの練習用。
+///
+/// `technique` の難易度の問題を `generate_with` で作り、テクニックで確定するマスを 1 つずつ埋めながら
+/// 条件を満たす局面を探す。見つかった局面の数字をすべて問題数字とする。
+/// 特定の形を探すので、難易度だけを指定する生成より遅い。最大 `DRILL_ATTEMPTS` 問試して
+/// 見つからなければ `None`。
+pub fn generate_technique_drill(seed: u64, technique: Technique) -> Option<(Board, Board)> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let options = GenerateOptions {
+        clues: DRILL_CLUES,
+        difficulty: Some(technique.difficulty()),
+        symmetry: None,
+        logical_only: true,
+        max_candidates: None,
+    };
+
+    for _ in 0..DRILL_ATTEMPTS {
+        let Some((mut board, solution, _)) = generate_with(rng.gen(), &options) else {
+            continue;
+        };
+        while let Some(step) = techniques::easiest_step(&board) {
+            if step.technique == technique {
+                return Some((board, solution));
+            }
+            let Some((sq, num, _)) = techniques::next_placement(&board) else {
+                break;
+            };
+            board[sq] = Some(num);
+        }
+    }
+
+    None
+}
+
+/// 空マスの候補の個数の最大値。空マスがなければ 0。
+fn max_candidate_count(board: &Board) -> usize {
+    board
+        .iter()
+        .map(|(sq, _)| board.candidates(sq).len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// 対称性 `symmetry` で互いに移り合うマスの組。対称性がなければ 1 マスずつ。
+fn symmetry_orbits(symmetry: Option<Symmetry>) -> Vec<Vec<Square>> {
+    let mut seen = [false; 81];
+    let mut orbits = vec![];
+    for sq in Square::all() {
+        if seen[sq.index()] {
+            continue;
+        }
+        let mut orbit = vec![sq];
+        seen[sq.index()] = true;
+        if let Some(symmetry) = symmetry {
+            let mut next = symmetry.map(sq);
+            while next != sq {
+                orbit.push(next);
+                seen[next.index()] = true;
+                next = symmetry.map(next);
+            }
+        }
+        orbits.push(orbit);
+    }
+    orbits
+}
+
+fn count_clues(board: &Board) -> usize {
+    board.iter().filter(|(_, num)| num.is_some()).count()
+}
+
+/// 問題生成の統計。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GenerationStats {
+    /// マスを消そうと試みた回数(= 唯一性判定の回数)。
+    pub attempts: usize,
+    /// 生成された問題のヒント数。
+    pub clues: usize,
+    /// 生成された問題の難易度。難易度を条件にした `generate_with` でだけ求める。
+    pub difficulty: Option<Difficulty>,
+    pub elapsed: Duration,
+}
+
+fn random_solution(rng: &mut impl Rng) -> Board {
+    let base = solver::solve(&Board::empty()).unwrap();
+
+    let mut nums = Number::all();
+    nums.shuffle(rng);
+    let rows = random_line_order(rng);
+    let cols = random_line_order(rng);
+    let transpose = rng.gen_bool(0.5);
+
+    let mut board = Board::empty();
+    for sq in Square::all() {
+        let (mut r, mut c) = (sq.row_index(), sq.col_index());
+        if transpose {
+            std::mem::swap(&mut r, &mut c);
+        }
+        let src = Square::from_col_row(Col::all()[cols[c]], Row::all()[rows[r]]);
+        board[sq] = base[src];
+    }
+
+    board.relabel(nums)
+}
+
+/// バンド(3 行ずつの組)単位とバンド内でそれぞれシャッフルした行(列)の並びを返す。
+fn random_line_order(rng: &mut impl Rng) -> [usize; 9] {
+    let mut bands = [0, 1, 2];
+    bands.shuffle(rng);
+
+    let mut order = [0; 9];
+    for (i, band) in bands.into_iter().enumerate() {
+        let mut lines = [0, 1, 2];
+        lines.shuffle(rng);
+        for (j, line) in lines.into_iter().enumerate() {
+            order[3 * i + j] = 3 * band + line;
+        }
+    }
+
+    order
+}
+
+/// 問題と、マスを消そうと試みた回数を返す。
+fn dig(solution: &Board, clues: usize, rng: &mut impl Rng) -> (Board, usize) {
+    let mut squares = Square::all();
+    squares.shuffle(rng);
+
+    let mut board = solution.clone();
+    let mut count = 81;
+    let mut attempts = 0;
+    for sq in squares {
+        if count <= clues {
+            break;
+        }
+        attempts += 1;
+        let num = board[sq].take();
+        if solver::count_solutions(&board, 2) == 1 {
+            count -= 1;
+        } else {
+            board[sq] = num;
+        }
+    }
+
+    (board, attempts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 市松模様(41 マス)。
+    fn checkerboard() -> Vec<Square> {
+        Square::all()
+            .into_iter()
+            .filter(|sq| (sq.row_index() + sq.col_index()) % 2 == 0)
+            .collect()
+    }
+
+    #[test]
+    fn generate_with_pattern_seeded_fills_exactly_the_pattern() {
+        let squares = checkerboard();
+        for seed in 0..5 {
+            let (puzzle, solution) = generate_with_pattern_seeded(seed, &squares, 100)
+                .expect("checkerboard puzzle should be found");
+            for sq in Square::all() {
+                if squares.contains(&sq) {
+                    assert_eq!(puzzle[sq], solution[sq], "seed {seed}, {sq:?}");
+                } else {
+                    assert_eq!(puzzle[sq], None, "seed {seed}, {sq:?}");
+                }
+            }
+            assert_eq!(solver::count_solutions(&puzzle, 2), 1, "seed {seed}");
+            assert!(puzzle.symmetry().contains(Symmetry::Rotate180));
+        }
+    }
+
+    #[test]
+    fn generate_with_pattern_seeded_gives_up_on_empty_pattern() {
+        assert!(generate_with_pattern_seeded(0, &[], 3).is_none());
+    }
+
+    fn assert_generated_unique(count: u64) {
+        for seed in 0..count {
+            let (puzzle, solution) = generate_seeded(seed, 30);
+            assert_eq!(solver::count_solutions(&puzzle, 2), 1, "seed {seed}");
+            assert!(solution.is_full() && solution.is_valid(), "seed {seed}");
+            assert!(
+                puzzle
+                    .iter()
+                    .all(|(sq, cell)| cell.is_none() || cell == solution[sq]),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn generated_puzzles_are_unique() {
+        assert_generated_unique(50);
+    }
+
+    /// 時間がかかるので `cargo test -- --ignored` で走らせる。
+    #[test]
+    #[ignore]
+    fn many_generated_puzzles_are_unique() {
+        assert_generated_unique(500);
+    }
+
+    fn options(max_candidates: Option<usize>) -> GenerateOptions {
+        GenerateOptions {
+            clues: 30,
+            difficulty: None,
+            symmetry: None,
+            logical_only: false,
+            max_candidates,
+        }
+    }
+
+    #[test]
+    fn generate_with_respects_max_candidates() {
+        for max in [2, 3, 4] {
+            for seed in 0..5 {
+                let (puzzle, solution, _) = generate_with(seed, &options(Some(max)))
+                    .unwrap_or_else(|| panic!("max {max}, seed {seed}: not generated"));
+                assert!(
+                    max_candidate_count(&puzzle) <= max,
+                    "max {max}, seed {seed}"
+                );
+                for sq in Square::all() {
+                    if puzzle[sq].is_none() {
+                        assert!(puzzle.candidates(sq).len() <= max, "max {max}, seed {seed}");
+                    }
+                }
+                assert_eq!(solver::count_solutions(&puzzle, 2), 1);
+                assert!(puzzle
+                    .iter()
+                    .all(|(sq, cell)| cell.is_none() || cell == solution[sq]));
+            }
+        }
+    }
+
+    #[test]
+    fn max_candidate_count_of_boards() {
+        assert_eq!(max_candidate_count(&Board::empty()), 9);
+        let (puzzle, solution) = generate_seeded(0, 30);
+        assert_eq!(max_candidate_count(&solution), 0);
+        let expected = Square::all()
+            .into_iter()
+            .filter(|&sq| puzzle[sq].is_none())
+            .map(|sq| puzzle.candidates(sq).len())
+            .max()
+            .unwrap();
+        assert_eq!(max_candidate_count(&puzzle), expected);
+    }
+
+    #[test]
+    fn generate_logical_respects_max_difficulty() {
+        for max in [Difficulty::Medium, Difficulty::Hard] {
+            for seed in 0..3 {
+                let (puzzle, solution, stats) = generate_logical(seed, 35, max)
+                    .unwrap_or_else(|| panic!("max {max:?}, seed {seed}: not generated"));
+                let log = techniques::solve_logically(&puzzle);
+                assert!(log.solved, "max {max:?}, seed {seed}: needs guessing");
+                assert_eq!(log.board.key(), solution.key(), "max {max:?}, seed {seed}");
+                assert!(techniques::rate(&puzzle) <= max, "max {max:?}, seed {seed}");
+                assert_eq!(stats.difficulty, Some(techniques::rate(&puzzle)));
+                assert_eq!(solver::count_solutions(&puzzle, 2), 1);
+            }
+        }
+    }
+}
@@ -1,3 +1,15 @@
+pub mod board;
+pub mod game;
+pub mod generate;
+pub mod number_set;
+pub mod score;
+pub mod share;
+pub mod solver;
+pub mod square;
+pub mod symmetry;
+pub mod techniques;
+#[cfg(feature = "web")]
 mod web;
 
+#[cfg(feature = "web")]
 pub use self::web::start;
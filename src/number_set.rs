@@ -0,0 +1,217 @@
+use sudoku::*;
+
+/// 数字の集合。bit `n - 1` が数字 `n` に対応する。
+///
+/// 走査は常に小さい数字から順に行う。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct NumberSet(u16);
+
+impl NumberSet {
+    const ALL_BITS: u16 = (1 << 9) - 1;
+
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// 1-9 すべてを含む集合。
+    pub const fn all() -> Self {
+        Self(Self::ALL_BITS)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, num: Number) -> bool {
+        self.0 & bit(num) != 0
+    }
+
+    /// `num` を加える。もともと含まれていなければ `true`。
+    pub fn insert(&mut self, num: Number) -> bool {
+        let added = !self.contains(num);
+        self.0 |= bit(num);
+        added
+    }
+
+    /// `num` を除く。もともと含まれていれば `true`。
+    pub fn remove(&mut self, num: Number) -> bool {
+        let removed = self.contains(num);
+        self.0 &= !bit(num);
+        removed
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn iter(self) -> Iter {
+        Iter(self.0)
+    }
+
+    /// `current` の次に大きい数字(`up` でなければ次に小さい数字)。端まで来たら反対の端に戻る。
+    ///
+    /// `current` が `None` なら最小(`up` でなければ最大)の数字。集合が空なら `None`。
+    /// `current` 自体は集合に含まれていなくてよい。
+    pub fn cycle(self, current: Option<Number>, up: bool) -> Option<Number> {
+        let is_beyond = |num: Number| match current {
+            Some(cur) if up => num.get() > cur.get(),
+            Some(cur) => num.get() < cur.get(),
+            None => true,
+        };
+        let next = if up {
+            self.iter().find(|&num| is_beyond(num))
+        } else {
+            self.iter().filter(|&num| is_beyond(num)).last()
+        };
+        next.or_else(|| {
+            if up {
+                self.iter().next()
+            } else {
+                self.iter().last()
+            }
+        })
+    }
+}
+
+impl FromIterator<Number> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = Number>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for num in iter {
+            set.insert(num);
+        }
+        set
+    }
+}
+
+impl IntoIterator for NumberSet {
+    type Item = Number;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+/// `NumberSet::iter` の戻り値。
+#[derive(Clone, Debug)]
+pub struct Iter(u16);
+
+impl Iterator for Iter {
+    type Item = Number;
+
+    fn next(&mut self) -> Option<Number> {
+        if self.0 == 0 {
+            return None;
+        }
+        let i = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(Number::all()[i])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+fn bit(num: Number) -> u16 {
+    1 << (num.get() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(n: u8) -> Number {
+        Number::all()[usize::from(n) - 1]
+    }
+
+    fn set(ns: &[u8]) -> NumberSet {
+        ns.iter().map(|&n| num(n)).collect()
+    }
+
+    fn values(set: NumberSet) -> Vec<u8> {
+        set.iter().map(Number::get).collect()
+    }
+
+    #[test]
+    fn new_is_empty_and_all_is_full() {
+        assert!(NumberSet::new().is_empty());
+        assert_eq!(NumberSet::new().len(), 0);
+        assert_eq!(NumberSet::all().len(), 9);
+        assert_eq!(values(NumberSet::all()), (1..=9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_and_remove_report_changes() {
+        let mut s = NumberSet::new();
+        assert!(s.insert(num(3)));
+        assert!(!s.insert(num(3)));
+        assert!(s.contains(num(3)));
+        assert!(!s.contains(num(4)));
+        assert!(s.remove(num(3)));
+        assert!(!s.remove(num(3)));
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn iterates_in_ascending_order() {
+        assert_eq!(values(set(&[9, 1, 5, 3])), [1, 3, 5, 9]);
+        assert_eq!(set(&[9, 1, 5, 3]).iter().len(), 4);
+    }
+
+    #[test]
+    fn cycle_from_none_starts_at_an_end() {
+        let s = set(&[2, 5, 7]);
+        assert_eq!(s.cycle(None, true), Some(num(2)));
+        assert_eq!(s.cycle(None, false), Some(num(7)));
+    }
+
+    #[test]
+    fn cycle_from_some_moves_to_neighbor() {
+        let s = set(&[2, 5, 7]);
+        assert_eq!(s.cycle(Some(num(2)), true), Some(num(5)));
+        assert_eq!(s.cycle(Some(num(5)), false), Some(num(2)));
+        // `current` が集合になくても、その前後の数字に移る。
+        assert_eq!(s.cycle(Some(num(6)), true), Some(num(7)));
+        assert_eq!(s.cycle(Some(num(6)), false), Some(num(5)));
+    }
+
+    #[test]
+    fn cycle_wraps_around() {
+        let s = set(&[2, 5, 7]);
+        assert_eq!(s.cycle(Some(num(7)), true), Some(num(2)));
+        assert_eq!(s.cycle(Some(num(2)), false), Some(num(7)));
+        assert_eq!(set(&[4]).cycle(Some(num(4)), true), Some(num(4)));
+    }
+
+    #[test]
+    fn cycle_of_empty_set_is_none() {
+        assert_eq!(NumberSet::new().cycle(None, true), None);
+        assert_eq!(NumberSet::new().cycle(Some(num(3)), false), None);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[1, 2, 3, 4]);
+        let b = set(&[3, 4, 5]);
+        assert_eq!(values(a.union(b)), [1, 2, 3, 4, 5]);
+        assert_eq!(values(a.intersection(b)), [3, 4]);
+        assert_eq!(values(a.difference(b)), [1, 2]);
+        assert_eq!(values(b.difference(a)), [5]);
+    }
+}
@@ -0,0 +1,65 @@
+//! クリア時のスコア計算。
+
+use std::time::Duration;
+
+use crate::techniques::Difficulty;
+
+/// 難易度・クリアタイム・ミス数・ヒント数からスコアを計算する。
+///
+/// 難易度ごとの基本点を、クリアタイムが 30 分で半分になるように割り引き、
+/// そこからミス 1 回につき 100 点、ヒント 1 回につき 200 点を引く(0 未満にはならない)。
+pub fn score(difficulty: Difficulty, dur: Duration, miss_count: u32, hint_count: u32) -> u32 {
+    const HALF_SECS: u64 = 30 * 60;
+
+    let base: u64 = match difficulty {
+        Difficulty::Easy => 1000,
+        Difficulty::Medium => 2000,
+        Difficulty::Hard => 3000,
+        Difficulty::Expert => 5000,
+    };
+    let timed = base * HALF_SECS / (HALF_SECS + dur.as_secs());
+    let penalty = 100 * u64::from(miss_count) + 200 * u64::from(hint_count);
+
+    timed.saturating_sub(penalty) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instant_clear_scores_base() {
+        assert_eq!(score(Difficulty::Easy, Duration::ZERO, 0, 0), 1000);
+        assert_eq!(score(Difficulty::Medium, Duration::ZERO, 0, 0), 2000);
+        assert_eq!(score(Difficulty::Hard, Duration::ZERO, 0, 0), 3000);
+        assert_eq!(score(Difficulty::Expert, Duration::ZERO, 0, 0), 5000);
+    }
+
+    #[test]
+    fn thirty_minutes_halves_score() {
+        let dur = Duration::from_secs(30 * 60);
+        assert_eq!(score(Difficulty::Hard, dur, 0, 0), 1500);
+    }
+
+    #[test]
+    fn harder_and_faster_score_higher() {
+        let dur = Duration::from_secs(600);
+        assert!(score(Difficulty::Expert, dur, 0, 0) > score(Difficulty::Hard, dur, 0, 0));
+        assert!(
+            score(Difficulty::Easy, Duration::from_secs(300), 0, 0)
+                > score(Difficulty::Easy, Duration::from_secs(900), 0, 0)
+        );
+    }
+
+    #[test]
+    fn misses_and_hints_are_penalized() {
+        assert_eq!(score(Difficulty::Medium, Duration::ZERO, 3, 0), 1700);
+        assert_eq!(score(Difficulty::Medium, Duration::ZERO, 0, 2), 1600);
+        assert_eq!(score(Difficulty::Medium, Duration::ZERO, 1, 1), 1700);
+    }
+
+    #[test]
+    fn score_does_not_go_below_zero() {
+        assert_eq!(score(Difficulty::Easy, Duration::ZERO, 100, 100), 0);
+    }
+}
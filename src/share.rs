@@ -0,0 +1,64 @@
+//! 問題を共有リンク(`?puzzle=...`)に載せるための文字列表現。
+//!
+//! 問題数字だけを扱い、入力やメモは含めない。
+
+use sudoku::*;
+
+use crate::board::{BoardExt, ParseBoardError};
+
+/// 問題数字の盤面を、`Square::all()` 順の 81 文字(空マスは `0`)にする。
+///
+/// 数字しか使わないので URL にそのまま載せられる。`decode_puzzle` で元の盤面に戻る。
+pub fn encode_puzzle(board: &Board) -> String {
+    board.to_line().replace('.', "0")
+}
+
+/// `encode_puzzle` の出力を盤面に戻す。
+///
+/// `BoardExt::parse_flexible` でパースするので、空マスを `.` で書いた文字列なども受け付ける。
+pub fn decode_puzzle(s: &str) -> Result<Board, ParseBoardError> {
+    Board::parse_flexible(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[test]
+    fn encode_uses_only_digits() {
+        let board = Board::from_line(PUZZLE).unwrap();
+        let encoded = encode_puzzle(&board);
+        assert_eq!(encoded, PUZZLE.replace('.', "0"));
+        assert!(encoded.chars().all(|ch| ch.is_ascii_digit()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let boards = [Board::from_line(PUZZLE).unwrap(), Board::empty()]
+            .into_iter()
+            .chain((0..20).flat_map(|seed| {
+                let (puzzle, solution) = generate::generate_seeded(seed, 30);
+                [puzzle, solution]
+            }));
+        for board in boards {
+            let decoded = decode_puzzle(&encode_puzzle(&board)).unwrap();
+            assert_eq!(decoded.key(), board.key());
+        }
+    }
+
+    #[test]
+    fn decode_rejects_broken_links() {
+        assert_eq!(
+            decode_puzzle(&PUZZLE[..40]).map(|_| ()),
+            Err(ParseBoardError::CellCount { count: 40 })
+        );
+        assert_eq!(
+            decode_puzzle("abc").map(|_| ()),
+            Err(ParseBoardError::InvalidChar { ch: 'a' })
+        );
+    }
+}
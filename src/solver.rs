@@ -0,0 +1,209 @@
+//! 乱数を使わない決定的なソルバー。
+//!
+//! 探索順は固定で、各ノードで候補数が最小の空マス(同数なら `Square::all()` 順で先のもの)を選び、
+//! その候補を小さい数字から順に試す。よって同じ盤面に対しては何度呼んでも同じ結果・同じ探索量になる。
+//! 問題生成側の乱数とは独立している。
+
+use sudoku::*;
+
+use crate::number_set::NumberSet;
+use crate::square::SquareExt;
+
+/// 盤面の解を 1 つ返す。解がなければ `None`。
+pub fn solve(board: &Board) -> Option<Board> {
+    solve_with_effort(board).0
+}
+
+/// `solve` と同じ探索を行い、解とともに推測の回数を返す。
+///
+/// 推測の回数は、候補が 2 つ以上あるマスに数字を仮置きした回数(最初の解が見つかるまで)。
+/// 探索順が固定なので同じ盤面なら常に同じ値になり、大まかな難しさの目安になる。
+pub fn solve_with_effort(board: &Board) -> (Option<Board>, u64) {
+    let mut solution = None;
+    let guesses = search(board, |b| {
+        solution = Some(b.clone());
+        false
+    });
+    (solution, guesses)
+}
+
+/// 盤面の解の個数を数える。`limit` 個見つかった時点で打ち切る。
+pub fn count_solutions(board: &Board, limit: usize) -> usize {
+    if limit == 0 {
+        return 0;
+    }
+
+    let mut count = 0;
+    search(board, |_| {
+        count += 1;
+        count < limit
+    });
+    count
+}
+
+/// 解の個数による盤面の分類。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Classification {
+    Unsolvable,
+    Unique,
+    Multiple,
+}
+
+pub fn classify(board: &Board) -> Classification {
+    match count_solutions(board, 2) {
+        0 => Classification::Unsolvable,
+        1 => Classification::Unique,
+        _ => Classification::Multiple,
+    }
+}
+
+/// 深さ優先探索を行い、解を見つけるたびに `visit` を呼ぶ。`visit` が `false` を返したら打ち切る。
+///
+/// 推測(候補が 2 つ以上あるマスへの仮置き)の回数を返す。
+fn search(board: &Board, mut visit: impl FnMut(&Board) -> bool) -> u64 {
+    let Some(mut masks) = Masks::new(board) else {
+        return 0;
+    };
+    let mut board = board.clone();
+    let mut guesses = 0;
+    search_rec(&mut board, &mut masks, &mut visit, &mut guesses);
+    guesses
+}
+
+fn search_rec(
+    board: &mut Board,
+    masks: &mut Masks,
+    visit: &mut impl FnMut(&Board) -> bool,
+    guesses: &mut u64,
+) -> bool {
+    let mut best: Option<(Square, NumberSet)> = None;
+    for sq in Square::all() {
+        if board[sq].is_some() {
+            continue;
+        }
+        let cands = masks.candidates(sq);
+        if cands.is_empty() {
+            return true;
+        }
+        let is_better = match best {
+            Some((_, best_cands)) => cands.len() < best_cands.len(),
+            None => true,
+        };
+        if is_better {
+            best = Some((sq, cands));
+        }
+    }
+
+    let Some((sq, cands)) = best else {
+        return visit(board);
+    };
+
+    let is_guess = cands.len() > 1;
+    for num in cands {
+        if is_guess {
+            *guesses += 1;
+        }
+        board[sq] = Some(num);
+        masks.insert(sq, num);
+        let cont = search_rec(board, masks, visit, guesses);
+        masks.remove(sq, num);
+        board[sq] = None;
+        if !cont {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// 各行/列/ブロックで使われている数字。
+struct Masks {
+    rows: [NumberSet; 9],
+    cols: [NumberSet; 9],
+    blocks: [NumberSet; 9],
+}
+
+impl Masks {
+    /// 盤面に矛盾があれば `None`。
+    fn new(board: &Board) -> Option<Self> {
+        let mut masks = Self {
+            rows: [NumberSet::new(); 9],
+            cols: [NumberSet::new(); 9],
+            blocks: [NumberSet::new(); 9],
+        };
+
+        for sq in Square::all() {
+            let Some(num) = board[sq] else {
+                continue;
+            };
+            if masks.used(sq).contains(num) {
+                return None;
+            }
+            masks.insert(sq, num);
+        }
+
+        Some(masks)
+    }
+
+    fn used(&self, sq: Square) -> NumberSet {
+        let (r, c, b) = unit_indices(sq);
+        self.rows[r].union(self.cols[c]).union(self.blocks[b])
+    }
+
+    fn candidates(&self, sq: Square) -> NumberSet {
+        NumberSet::all().difference(self.used(sq))
+    }
+
+    fn insert(&mut self, sq: Square, num: Number) {
+        let (r, c, b) = unit_indices(sq);
+        self.rows[r].insert(num);
+        self.cols[c].insert(num);
+        self.blocks[b].insert(num);
+    }
+
+    fn remove(&mut self, sq: Square, num: Number) {
+        let (r, c, b) = unit_indices(sq);
+        self.rows[r].remove(num);
+        self.cols[c].remove(num);
+        self.blocks[b].remove(num);
+    }
+}
+
+fn unit_indices(sq: Square) -> (usize, usize, usize) {
+    (sq.row_index(), sq.col_index(), sq.block_index())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoardExt;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    #[test]
+    fn solves_sample_puzzle() {
+        let puzzle = Board::from_line(PUZZLE).unwrap();
+        let solution = solve(&puzzle).unwrap();
+        assert_eq!(solution.to_line(), SOLUTION);
+        assert_eq!(classify(&puzzle), Classification::Unique);
+    }
+
+    #[test]
+    fn counts_multiple_solutions_up_to_limit() {
+        assert_eq!(count_solutions(&Board::empty(), 3), 3);
+        assert_eq!(count_solutions(&Board::empty(), 0), 0);
+        assert_eq!(classify(&Board::empty()), Classification::Multiple);
+    }
+
+    #[test]
+    fn rejects_conflicting_board() {
+        let mut line = PUZZLE.to_owned();
+        line.replace_range(2..3, "5");
+        let board = Board::from_line(&line).unwrap();
+        assert!(solve(&board).is_none());
+        assert_eq!(classify(&board), Classification::Unsolvable);
+    }
+}
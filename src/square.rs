@@ -0,0 +1,218 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use sudoku::*;
+
+/// マスの添字。
+///
+/// いずれも 0-origin の `usize` で、`Row::get()`/`Col::get()`/`Block::get()` (0-origin) と一致する。
+/// 1-origin なのは `Number::get()` と `Label` の表記だけ。
+pub trait SquareExt {
+    /// 行優先の通し番号 `row_index() * 9 + col_index()` (0..81)。
+    fn index(self) -> usize;
+
+    /// 行番号 (0..9、上から)。
+    fn row_index(self) -> usize;
+
+    /// 列番号 (0..9、左から)。
+    fn col_index(self) -> usize;
+
+    /// ブロック番号 (0..9、左上から右へ、上から下へ)。
+    fn block_index(self) -> usize;
+
+    /// 行/列/ブロックのいずれかを共有するなら `true`(自分自身とも共有する)。
+    fn shares_unit_with(self, other: Square) -> bool;
+
+    /// `self` 以外で行/列/ブロックを共有する 20 マスを `Square::all()` 順に返す。
+    ///
+    /// 全マス分の表を初回の呼び出しで作り、以降はそれを引くだけ。
+    fn peers(self) -> [Square; 20];
+
+    /// `drow` 行下、`dcol` 列右のマス。盤面の外に出るなら `None`。
+    fn offset(self, drow: isize, dcol: isize) -> Option<Square>;
+
+    /// `offset` と同じだが、盤面の外に出る分は反対の端へ折り返す(行と列で別々に)。
+    fn offset_wrapping(self, drow: isize, dcol: isize) -> Square;
+}
+
+impl SquareExt for Square {
+    fn index(self) -> usize {
+        self.row_index() * 9 + self.col_index()
+    }
+
+    fn row_index(self) -> usize {
+        usize::from(self.row().get())
+    }
+
+    fn col_index(self) -> usize {
+        usize::from(self.col().get())
+    }
+
+    fn block_index(self) -> usize {
+        self.row_index() / 3 * 3 + self.col_index() / 3
+    }
+
+    fn shares_unit_with(self, other: Square) -> bool {
+        self.row() == other.row() || self.col() == other.col() || self.block() == other.block()
+    }
+
+    fn peers(self) -> [Square; 20] {
+        static PEERS: OnceLock<[[Square; 20]; 81]> = OnceLock::new();
+
+        let table = PEERS.get_or_init(|| {
+            Square::all().map(|sq| {
+                let mut peers = Square::all()
+                    .into_iter()
+                    .filter(|&other| other != sq && sq.shares_unit_with(other));
+                std::array::from_fn(|_| peers.next().unwrap())
+            })
+        });
+        table[self.index()]
+    }
+
+    fn offset(self, drow: isize, dcol: isize) -> Option<Square> {
+        let r = self
+            .row_index()
+            .checked_add_signed(drow)
+            .filter(|&r| r < 9)?;
+        let c = self
+            .col_index()
+            .checked_add_signed(dcol)
+            .filter(|&c| c < 9)?;
+        Some(Square::from_col_row(Col::all()[c], Row::all()[r]))
+    }
+
+    fn offset_wrapping(self, drow: isize, dcol: isize) -> Square {
+        let r = (self.row_index() as isize + drow).rem_euclid(9) as usize;
+        let c = (self.col_index() as isize + dcol).rem_euclid(9) as usize;
+        Square::from_col_row(Col::all()[c], Row::all()[r])
+    }
+}
+
+/// 行・列・ブロック・マスの人間向け表記。
+///
+/// 行/列/ブロック番号はいずれも 1-origin で、マスは `R4C7` (4 行目 7 列目)、
+/// 行は `R4`、列は `C7`、ブロックは `B3` (左上から右へ、上から下へ数える)と表記する。
+pub trait Label {
+    fn label(&self) -> String;
+}
+
+impl Label for Row {
+    fn label(&self) -> String {
+        format!("R{}", self.get() + 1)
+    }
+}
+
+impl Label for Col {
+    fn label(&self) -> String {
+        format!("C{}", self.get() + 1)
+    }
+}
+
+impl Label for Block {
+    fn label(&self) -> String {
+        format!("B{}", self.get() + 1)
+    }
+}
+
+impl Label for Square {
+    fn label(&self) -> String {
+        format!("{}{}", self.row().label(), self.col().label())
+    }
+}
+
+/// 行 `row` のマスを左から順に返す。
+pub fn row_squares(row: Row) -> [Square; 9] {
+    std::array::from_fn(|i| Square::from_col_row(Col::all()[i], row))
+}
+
+/// 列 `col` のマスを上から順に返す。
+pub fn col_squares(col: Col) -> [Square; 9] {
+    std::array::from_fn(|i| Square::from_col_row(col, Row::all()[i]))
+}
+
+/// ブロック `block` のマスを左上から行優先で返す。
+pub fn block_squares(block: Block) -> [Square; 9] {
+    let b = usize::from(block.get());
+    std::array::from_fn(|i| {
+        let col = Col::all()[b % 3 * 3 + i % 3];
+        let row = Row::all()[b / 3 * 3 + i / 3];
+        Square::from_col_row(col, row)
+    })
+}
+
+/// `R4C7` 形式 (大文字小文字は問わない) のマス表記をパースする。
+pub fn parse_square_label(s: &str) -> Result<Square, ParseSquareError> {
+    let err = || ParseSquareError(s.to_owned());
+
+    let s_upper = s.to_ascii_uppercase();
+    let (row, col) = s_upper
+        .strip_prefix('R')
+        .and_then(|rest| rest.split_once('C'))
+        .ok_or_else(err)?;
+    let parse_index = |t: &str| match t.parse::<usize>() {
+        Ok(i @ 1..=9) => Ok(i - 1),
+        _ => Err(err()),
+    };
+    let row = Row::all()[parse_index(row)?];
+    let col = Col::all()[parse_index(col)?];
+
+    Ok(Square::from_col_row(col, row))
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseSquareError(String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid square label: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(row: usize, col: usize) -> Square {
+        Square::from_col_row(Col::all()[col], Row::all()[row])
+    }
+
+    #[test]
+    fn shares_unit_with_same_row_only() {
+        assert!(sq(0, 0).shares_unit_with(sq(0, 8)));
+    }
+
+    #[test]
+    fn shares_unit_with_same_col_only() {
+        assert!(sq(0, 4).shares_unit_with(sq(8, 4)));
+    }
+
+    #[test]
+    fn shares_unit_with_same_block_only() {
+        assert!(sq(3, 3).shares_unit_with(sq(5, 5)));
+    }
+
+    #[test]
+    fn shares_unit_with_unrelated() {
+        assert!(!sq(0, 0).shares_unit_with(sq(4, 4)));
+        assert!(!sq(2, 3).shares_unit_with(sq(3, 2)));
+    }
+
+    #[test]
+    fn shares_unit_with_self() {
+        assert!(sq(4, 4).shares_unit_with(sq(4, 4)));
+    }
+
+    #[test]
+    fn peers_are_the_sharing_squares_in_order() {
+        for s in Square::all() {
+            let expected: Vec<_> = Square::all()
+                .into_iter()
+                .filter(|&other| other != s && s.shares_unit_with(other))
+                .collect();
+            assert_eq!(s.peers().to_vec(), expected, "{}", s.label());
+        }
+    }
+}
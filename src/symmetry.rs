@@ -0,0 +1,91 @@
+use sudoku::*;
+
+use crate::square::SquareExt;
+
+/// 盤面の対称性。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Symmetry {
+    /// 180° 回転。
+    Rotate180,
+    /// 90° 回転。
+    Rotate90,
+    /// 左右反転(中央の列を軸とする)。
+    MirrorHorizontal,
+    /// 上下反転(中央の行を軸とする)。
+    MirrorVertical,
+    /// 左上-右下の対角線を軸とする反転。
+    MirrorDiagonal,
+    /// 右上-左下の対角線を軸とする反転。
+    MirrorAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Self; 6] = [
+        Self::Rotate180,
+        Self::Rotate90,
+        Self::MirrorHorizontal,
+        Self::MirrorVertical,
+        Self::MirrorDiagonal,
+        Self::MirrorAntiDiagonal,
+    ];
+
+    /// `sq` がこの変換で移る先のマス。
+    pub fn map(self, sq: Square) -> Square {
+        let (r, c) = (sq.row_index(), sq.col_index());
+        let (r, c) = match self {
+            Self::Rotate180 => (8 - r, 8 - c),
+            Self::Rotate90 => (c, 8 - r),
+            Self::MirrorHorizontal => (r, 8 - c),
+            Self::MirrorVertical => (8 - r, c),
+            Self::MirrorDiagonal => (c, r),
+            Self::MirrorAntiDiagonal => (8 - c, 8 - r),
+        };
+        Square::from_col_row(Col::all()[c], Row::all()[r])
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// `Symmetry` の集合。走査は `Symmetry::ALL` の順。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SymmetrySet(u8);
+
+impl SymmetrySet {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, symmetry: Symmetry) -> bool {
+        self.0 & symmetry.bit() != 0
+    }
+
+    pub fn insert(&mut self, symmetry: Symmetry) {
+        self.0 |= symmetry.bit();
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Symmetry> {
+        Symmetry::ALL
+            .into_iter()
+            .filter(move |&symmetry| self.contains(symmetry))
+    }
+}
+
+impl FromIterator<Symmetry> for SymmetrySet {
+    fn from_iter<I: IntoIterator<Item = Symmetry>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for symmetry in iter {
+            set.insert(symmetry);
+        }
+        set
+    }
+}
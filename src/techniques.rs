@@ -0,0 +1,688 @@
+//! 人間向けの解法テクニックによる論理的な求解。
+
+use std::collections::BTreeMap;
+
+use sudoku::*;
+
+use crate::number_set::NumberSet;
+use crate::square::{block_squares, col_squares, row_squares, Label, SquareExt};
+
+/// 解法テクニック。易しい順に並んでいる。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "web", derive(serde::Deserialize, serde::Serialize))]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    NakedPair,
+}
+
+impl Technique {
+    /// 易しい順。
+    pub const ALL: [Self; 4] = [
+        Self::NakedSingle,
+        Self::HiddenSingle,
+        Self::LockedCandidates,
+        Self::NakedPair,
+    ];
+
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Self::NakedSingle | Self::HiddenSingle => Difficulty::Easy,
+            Self::LockedCandidates => Difficulty::Medium,
+            Self::NakedPair => Difficulty::Hard,
+        }
+    }
+}
+
+impl Label for Technique {
+    fn label(&self) -> String {
+        match self {
+            Self::NakedSingle => "ネイキッドシングル",
+            Self::HiddenSingle => "ヒドゥンシングル",
+            Self::LockedCandidates => "ロックされた候補",
+            Self::NakedPair => "ネイキッドペア",
+        }
+        .to_owned()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "web", derive(serde::Deserialize, serde::Serialize))]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    /// 実装済みのテクニックだけでは解けない(推測が必要)。
+    Expert,
+}
+
+/// テクニックを 1 回適用した結果。
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    /// 確定するマスと数字(シングル系のみ)。
+    pub placement: Option<(Square, Number)>,
+    /// 消去される候補。
+    pub eliminations: Vec<(Square, Number)>,
+}
+
+/// 盤面と、各空マスに残っている候補。
+#[derive(Clone, Debug)]
+pub struct Grid {
+    board: Board,
+    cands: [NumberSet; 81],
+}
+
+impl Grid {
+    /// 候補は盤面から素朴に計算する(行/列/ブロックにない数字)。
+    pub fn new(board: &Board) -> Self {
+        let mut cands = [NumberSet::new(); 81];
+        for sq in Square::all() {
+            if board[sq].is_some() {
+                continue;
+            }
+            let used: NumberSet = sq
+                .peers()
+                .into_iter()
+                .filter_map(|peer| board[peer])
+                .collect();
+            cands[sq.index()] = NumberSet::all().difference(used);
+        }
+
+        Self {
+            board: board.clone(),
+            cands,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn is_solved(&self) -> bool {
+        Square::all().into_iter().all(|sq| self.board[sq].is_some())
+    }
+
+    /// 空マス `sq` の候補を小さい順に返す。
+    pub fn candidates(&self, sq: Square) -> Vec<Number> {
+        self.cands[sq.index()].iter().collect()
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        if let Some((sq, num)) = step.placement {
+            self.board[sq] = Some(num);
+            self.cands[sq.index()] = NumberSet::new();
+            for peer in sq.peers() {
+                self.cands[peer.index()].remove(num);
+            }
+        }
+        for &(sq, num) in &step.eliminations {
+            self.cands[sq.index()].remove(num);
+        }
+    }
+
+    fn has_candidate(&self, sq: Square, num: Number) -> bool {
+        self.cands[sq.index()].contains(num)
+    }
+}
+
+/// 候補が 1 つしかない空マス。
+pub fn naked_single(grid: &Grid) -> Option<Step> {
+    Square::all().into_iter().find_map(|sq| {
+        let cands = grid.cands[sq.index()];
+        (cands.len() == 1).then(|| Step {
+            technique: Technique::NakedSingle,
+            placement: Some((sq, cands.iter().next().unwrap())),
+            eliminations: vec![],
+        })
+    })
+}
+
+/// ある行/列/ブロックの中で、ある数字を置けるマスが 1 つしかない。
+pub fn hidden_single(grid: &Grid) -> Option<Step> {
+    units().into_iter().find_map(|unit| {
+        Number::all().into_iter().find_map(|num| {
+            let mut it = unit.iter().filter(|&&sq| grid.has_candidate(sq, num));
+            match (it.next(), it.next()) {
+                (Some(&sq), None) => Some(Step {
+                    technique: Technique::HiddenSingle,
+                    placement: Some((sq, num)),
+                    eliminations: vec![],
+                }),
+                _ => None,
+            }
+        })
+    })
+}
+
+/// ある数字の候補が 2 つのユニットの共通部分に閉じ込められているとき、
+/// もう一方のユニットの残りからその数字を消す(pointing / claiming)。
+pub fn locked_candidates(grid: &Grid) -> Option<Step> {
+    let blocks = block_units();
+    let lines: Vec<_> = row_units().into_iter().chain(col_units()).collect();
+
+    // (候補を閉じ込める側, 消去される側) の組をすべて調べる。
+    let pairs = blocks
+        .iter()
+        .flat_map(|block| lines.iter().map(move |line| (block, line)))
+        .flat_map(|(block, line)| [(block, line), (line, block)]);
+
+    for (base, cover) in pairs {
+        for num in Number::all() {
+            let sqs: Vec<_> = base
+                .iter()
+                .copied()
+                .filter(|&sq| grid.has_candidate(sq, num))
+                .collect();
+            if sqs.len() < 2 || !sqs.iter().all(|sq| cover.contains(sq)) {
+                continue;
+            }
+            let eliminations: Vec<_> = cover
+                .iter()
+                .copied()
+                .filter(|sq| !base.contains(sq) && grid.has_candidate(*sq, num))
+                .map(|sq| (sq, num))
+                .collect();
+            if !eliminations.is_empty() {
+                return Some(Step {
+                    technique: Technique::LockedCandidates,
+                    placement: None,
+                    eliminations,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// あるユニットで 2 マスの候補が同じ 2 つの数字だけのとき、ユニットの残りからその 2 つを消す。
+pub fn naked_pair(grid: &Grid) -> Option<Step> {
+    for unit in units() {
+        for (i, &a) in unit.iter().enumerate() {
+            let cands = grid.cands[a.index()];
+            if cands.len() != 2 {
+                continue;
+            }
+            for &b in &unit[i + 1..] {
+                if grid.cands[b.index()] != cands {
+                    continue;
+                }
+                let eliminations: Vec<_> = unit
+                    .iter()
+                    .copied()
+                    .filter(|&sq| sq != a && sq != b)
+                    .flat_map(|sq| {
+                        cands
+                            .iter()
+                            .filter(move |&num| grid.has_candidate(sq, num))
+                            .map(move |num| (sq, num))
+                    })
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::NakedPair,
+                        placement: None,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// 盤面に対して最も易しい次の一手を返す。
+///
+/// テクニックを易しい順(ネイキッドシングル → ヒドゥンシングル → ロックされた候補 → ネイキッドペア)
+/// に試し、最初に適用できたものを返す。
+pub fn easiest_step(board: &Board) -> Option<Step> {
+    easiest_step_in(&Grid::new(board))
+}
+
+/// 最も易しい次の一手(`easiest_step`)の難易度。テクニックが尽きて推測しか残っていなければ `None`。
+///
+/// 易しい一手が残っているならヒントを出さずに考えてもらう、といったヒントの出し方の判断に使える。
+pub fn next_step_difficulty(board: &Board) -> Option<Difficulty> {
+    easiest_step(board).map(|step| step.technique.difficulty())
+}
+
+/// 最も易しいテクニックで次に確定できるマスと数字を、そこで使ったテクニックとともに返す。
+///
+/// 候補の消去だけのステップは内部で適用して先へ進む。
+pub fn next_placement(board: &Board) -> Option<(Square, Number, Technique)> {
+    let mut grid = Grid::new(board);
+    loop {
+        let step = easiest_step_in(&grid)?;
+        if let Some((sq, num)) = step.placement {
+            return Some((sq, num, step.technique));
+        }
+        grid.apply(&step);
+    }
+}
+
+/// テクニックを易しい順に試し、最初に適用できたものを返す。
+pub fn easiest_step_in(grid: &Grid) -> Option<Step> {
+    Technique::ALL
+        .into_iter()
+        .find_map(|technique| find_step(grid, technique))
+}
+
+pub fn find_step(grid: &Grid, technique: Technique) -> Option<Step> {
+    match technique {
+        Technique::NakedSingle => naked_single(grid),
+        Technique::HiddenSingle => hidden_single(grid),
+        Technique::LockedCandidates => locked_candidates(grid),
+        Technique::NakedPair => naked_pair(grid),
+    }
+}
+
+/// `solve_logically` の結果。
+#[derive(Clone, Debug)]
+pub struct SolveLog {
+    pub steps: Vec<Step>,
+    /// これ以上テクニックを適用できなくなった時点の盤面。
+    pub board: Board,
+    pub solved: bool,
+}
+
+impl SolveLog {
+    /// 最後まで解けた場合、使ったテクニックのうち最も難しいものの難易度。
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        if !self.solved {
+            return None;
+        }
+        self.steps
+            .iter()
+            .map(|step| step.technique.difficulty())
+            .max()
+            .or(Some(Difficulty::Easy))
+    }
+
+    /// テクニックごとの使用回数。一度も使わなかったテクニックは含まない。
+    pub fn technique_counts(&self) -> BTreeMap<Technique, usize> {
+        let mut counts = BTreeMap::new();
+        for step in &self.steps {
+            *counts.entry(step.technique).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// 手順を、外部のツールに渡せる `TraceStep` の列にする。
+    pub fn trace(&self) -> Vec<TraceStep> {
+        self.steps.iter().map(TraceStep::from).collect()
+    }
+}
+
+/// `SolveLog::trace` の 1 手分。マスは `Label` の表記(`R4C7`)、数字は 1-9 で持つ。
+///
+/// `web` フィーチャーでは serde でシリアライズできる。JSON にすると次の形になる:
+///
+/// ```text
+/// {
+///   "technique": "LockedCandidates",
+///   "square": null,
+///   "number": null,
+///   "eliminations": [{ "square": "R4C8", "number": 5 }, ...]
+/// }
+/// ```
+///
+/// `technique` は `Technique` のバリアント名。`square`/`number` は確定するマスと数字で、
+/// シングル系以外では `null`。`eliminations` は消去される候補で、なければ空配列。
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "web", derive(serde::Deserialize, serde::Serialize))]
+pub struct TraceStep {
+    pub technique: Technique,
+    pub square: Option<String>,
+    pub number: Option<u8>,
+    pub eliminations: Vec<TraceElimination>,
+}
+
+/// `TraceStep::eliminations` の要素。
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "web", derive(serde::Deserialize, serde::Serialize))]
+pub struct TraceElimination {
+    pub square: String,
+    pub number: u8,
+}
+
+impl From<&Step> for TraceStep {
+    fn from(step: &Step) -> Self {
+        Self {
+            technique: step.technique,
+            square: step.placement.map(|(sq, _)| sq.label()),
+            number: step.placement.map(|(_, num)| num.get()),
+            eliminations: step
+                .eliminations
+                .iter()
+                .map(|&(sq, num)| TraceElimination {
+                    square: sq.label(),
+                    number: num.get(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `rate` が `solve_logically_bounded` に渡す手数の上限。
+///
+/// 1 手で候補が少なくとも 1 つ減るので、81 マス × 9 候補を超える手数はかからない。
+/// つまりこの上限はテクニックの実装の誤りで同じ手を繰り返したときの安全弁にすぎず、
+/// 正常な盤面では打ち切られない(手間を減らす効果はなく、`rate` の結果も上限なしの場合と同じ)。
+pub const RATE_MAX_STEPS: usize = 81 * 9;
+
+/// 盤面の難易度。テクニックだけで解けなければ `Difficulty::Expert`。
+pub fn rate(board: &Board) -> Difficulty {
+    solve_logically_bounded(board, RATE_MAX_STEPS)
+        .0
+        .difficulty()
+        .unwrap_or(Difficulty::Expert)
+}
+
+/// テクニックだけで解けるところまで解く。推測(バックトラック)は一切しない。
+pub fn solve_logically(board: &Board) -> SolveLog {
+    solve_logically_bounded(board, usize::MAX).0
+}
+
+/// `solve_logically` と同じだが、高々 `max_steps` 手で打ち切る。
+///
+/// 2 つ目の値は、打ち切られずに最後まで(解けるか、適用できるテクニックがなくなるまで)進めたかどうか。
+/// 打ち切られた場合の `SolveLog` はそこまでの途中経過で、`solved` は `false`。
+pub fn solve_logically_bounded(board: &Board, max_steps: usize) -> (SolveLog, bool) {
+    let mut grid = Grid::new(board);
+    let mut steps = vec![];
+
+    let completed = loop {
+        if grid.is_solved() {
+            break true;
+        }
+        let Some(step) = easiest_step_in(&grid) else {
+            break true;
+        };
+        if steps.len() == max_steps {
+            break false;
+        }
+        grid.apply(&step);
+        steps.push(step);
+    };
+
+    let log = SolveLog {
+        steps,
+        solved: grid.is_solved(),
+        board: grid.board,
+    };
+    (log, completed)
+}
+
+fn row_units() -> Vec<[Square; 9]> {
+    Row::all().into_iter().map(row_squares).collect()
+}
+
+fn col_units() -> Vec<[Square; 9]> {
+    Col::all().into_iter().map(col_squares).collect()
+}
+
+fn block_units() -> Vec<[Square; 9]> {
+    Block::all().into_iter().map(block_squares).collect()
+}
+
+fn units() -> Vec<[Square; 9]> {
+    row_units()
+        .into_iter()
+        .chain(col_units())
+        .chain(block_units())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoardExt;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn step(technique: Technique) -> Step {
+        Step {
+            technique,
+            placement: None,
+            eliminations: vec![],
+        }
+    }
+
+    #[test]
+    fn technique_counts_aggregates_steps() {
+        let log = SolveLog {
+            steps: vec![
+                step(Technique::NakedSingle),
+                step(Technique::LockedCandidates),
+                step(Technique::NakedSingle),
+                step(Technique::HiddenSingle),
+                step(Technique::NakedSingle),
+            ],
+            board: Board::empty(),
+            solved: false,
+        };
+        let counts = log.technique_counts();
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [
+                (Technique::NakedSingle, 3),
+                (Technique::HiddenSingle, 1),
+                (Technique::LockedCandidates, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn technique_counts_of_nearly_solved_board() {
+        // 2 マス空けただけなら、どちらもネイキッドシングルで埋まる。
+        let mut board = Board::from_line(SOLUTION).unwrap();
+        board[Square::all()[0]] = None;
+        board[Square::all()[80]] = None;
+        let log = solve_logically(&board);
+        assert!(log.solved);
+        assert_eq!(
+            log.technique_counts().into_iter().collect::<Vec<_>>(),
+            [(Technique::NakedSingle, 2)]
+        );
+    }
+
+    #[test]
+    fn technique_counts_sum_to_step_count() {
+        let log = solve_logically(&Board::from_line(PUZZLE).unwrap());
+        assert!(log.solved);
+        assert_eq!(log.board.to_line(), SOLUTION);
+        let counts = log.technique_counts();
+        assert_eq!(counts.values().sum::<usize>(), log.steps.len());
+        assert!(counts.values().all(|&count| count > 0));
+    }
+
+    #[test]
+    fn technique_counts_of_solved_board_is_empty() {
+        let log = solve_logically(&Board::from_line(SOLUTION).unwrap());
+        assert!(log.solved);
+        assert!(log.technique_counts().is_empty());
+    }
+
+    /// Arto Inkala の 2012 年の問題。最初からどのテクニックも使えない。
+    const INKALA_2012: &str =
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400";
+
+    #[test]
+    fn easiest_step_prefers_easier_technique() {
+        let board = Board::parse_flexible(PUZZLE).unwrap();
+        let grid = Grid::new(&board);
+        // 難しいテクニックも使える局面で、易しいものが選ばれる。
+        assert!(locked_candidates(&grid).is_some());
+        let single = naked_single(&grid).unwrap();
+
+        let step = easiest_step(&board).unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.placement, single.placement);
+        assert_eq!(step.eliminations, single.eliminations);
+    }
+
+    #[test]
+    fn easiest_step_none_without_applicable_technique() {
+        let board = Board::parse_flexible(INKALA_2012).unwrap();
+        assert!(easiest_step(&board).is_none());
+        let solved = Board::parse_flexible(SOLUTION).unwrap();
+        assert!(easiest_step(&solved).is_none());
+    }
+
+    #[test]
+    fn solve_logically_bounded_truncates() {
+        let board = Board::parse_flexible(PUZZLE).unwrap();
+        for max_steps in [0, 1] {
+            let (log, completed) = solve_logically_bounded(&board, max_steps);
+            assert!(!completed, "max_steps {max_steps}");
+            assert!(!log.solved, "max_steps {max_steps}");
+            assert_eq!(log.steps.len(), max_steps);
+            assert_eq!(log.difficulty(), None);
+        }
+
+        let (log, completed) = solve_logically_bounded(&board, usize::MAX);
+        assert!(completed);
+        assert!(log.solved);
+        assert_eq!(log.board.to_line(), SOLUTION);
+        let (capped, completed) = solve_logically_bounded(&board, log.steps.len());
+        assert!(completed);
+        assert!(capped.solved);
+    }
+
+    #[test]
+    fn solve_logically_bounded_completes_without_steps() {
+        // 適用できるテクニックがなければ、上限 0 でも打ち切りにはならない。
+        for s in [INKALA_2012, SOLUTION] {
+            let board = Board::parse_flexible(s).unwrap();
+            let (log, completed) = solve_logically_bounded(&board, 0);
+            assert!(completed, "{s}");
+            assert!(log.steps.is_empty(), "{s}");
+            assert_eq!(log.solved, s == SOLUTION);
+        }
+    }
+
+    #[test]
+    fn next_step_difficulty_of_boards() {
+        let board = Board::parse_flexible(PUZZLE).unwrap();
+        assert_eq!(next_step_difficulty(&board), Some(Difficulty::Easy));
+
+        let solved = Board::parse_flexible(SOLUTION).unwrap();
+        assert_eq!(next_step_difficulty(&solved), None);
+
+        // シングルなどで進めるだけ進めても、推測しか残らない。
+        let stuck = solve_logically(&Board::parse_flexible(INKALA_2012).unwrap()).board;
+        assert!(stuck.iter().any(|(_, n)| n.is_none()));
+        assert_eq!(next_step_difficulty(&stuck), None);
+    }
+
+    fn placement_step() -> Step {
+        Step {
+            technique: Technique::HiddenSingle,
+            placement: Some((
+                Square::from_col_row(Col::all()[6], Row::all()[3]),
+                Number::all()[7],
+            )),
+            eliminations: vec![],
+        }
+    }
+
+    fn elimination_step() -> Step {
+        Step {
+            technique: Technique::LockedCandidates,
+            placement: None,
+            eliminations: vec![
+                (
+                    Square::from_col_row(Col::all()[7], Row::all()[3]),
+                    Number::all()[4],
+                ),
+                (
+                    Square::from_col_row(Col::all()[0], Row::all()[8]),
+                    Number::all()[0],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn trace_step_from_placement() {
+        let trace = TraceStep::from(&placement_step());
+        assert_eq!(
+            trace,
+            TraceStep {
+                technique: Technique::HiddenSingle,
+                square: Some("R4C7".to_owned()),
+                number: Some(8),
+                eliminations: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn trace_step_from_eliminations() {
+        let trace = TraceStep::from(&elimination_step());
+        assert_eq!(trace.technique, Technique::LockedCandidates);
+        assert_eq!(trace.square, None);
+        assert_eq!(trace.number, None);
+        assert_eq!(
+            trace.eliminations,
+            [
+                TraceElimination {
+                    square: "R4C8".to_owned(),
+                    number: 5,
+                },
+                TraceElimination {
+                    square: "R9C1".to_owned(),
+                    number: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_numbers_are_one_based() {
+        let board = Board::parse_flexible(PUZZLE).unwrap();
+        let trace = solve_logically(&board).trace();
+        assert!(!trace.is_empty());
+        for step in &trace {
+            assert!(step.number.iter().all(|n| (1..=9).contains(n)));
+            assert!(step
+                .eliminations
+                .iter()
+                .all(|e| (1..=9).contains(&e.number)));
+        }
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn trace_step_json_shape() {
+        let trace = TraceStep::from(&elimination_step());
+        let json = serde_json::to_value(&trace).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "technique": "LockedCandidates",
+                "square": null,
+                "number": null,
+                "eliminations": [
+                    { "square": "R4C8", "number": 5 },
+                    { "square": "R9C1", "number": 1 }
+                ]
+            })
+        );
+        assert_eq!(serde_json::from_value::<TraceStep>(json).unwrap(), trace);
+
+        let trace = TraceStep::from(&placement_step());
+        let s = serde_json::to_string(&trace).unwrap();
+        assert_eq!(
+            s,
+            r#"{"technique":"HiddenSingle","square":"R4C7","number":8,"eliminations":[]}"#
+        );
+        assert_eq!(serde_json::from_str::<TraceStep>(&s).unwrap(), trace);
+    }
+}
@@ -1,176 +1,2306 @@
 use instant::{Duration, Instant};
 use seed::{prelude::*, *};
+use serde::{Deserialize, Serialize};
 
 use sudoku::*;
 
+use crate::board::BoardExt;
+use crate::game::{Game, Move, SudokuExt};
+use crate::generate::PuzzleMeta;
+use crate::number_set::NumberSet;
+use crate::score::score;
+use crate::solver::{self, Classification};
+use crate::square::{row_squares, Label, SquareExt};
+use crate::techniques::{self, Difficulty, Grid, Technique};
+
 #[wasm_bindgen(start)]
 pub fn start() {
     App::start("app", init, update, view);
 }
 
-#[derive(Debug)]
-struct Model {
-    sudoku: Sudoku,
-    solution: Board,
-    sq_selected: Option<Square>,
-    sq_missed: Option<Square>,
-    miss_count: u32,
-    state: State,
+#[derive(Debug)]
+struct Model {
+    sudoku: Game,
+    /// 最後に選択したマス。数字の入力先になる。
+    sq_selected: Option<Square>,
+    /// 選択中のマスすべて(`sq_selected` を含む)。Ctrl+クリックで複数選択できる。
+    selection: Vec<Square>,
+    /// マウスカーソルが乗っているマス。
+    sq_hover: Option<Square>,
+    /// タッチ操作の開始位置。
+    touch: Option<Touch>,
+    sq_missed: Option<Square>,
+    /// 直前のミス。次の操作で消える。
+    last_miss: Option<Miss>,
+    /// 同じマスに同じ誤った数字を続けて入れた回数 `(sq, num, 回数)`。
+    repeated_miss: Option<(Square, Number, u32)>,
+    /// 数字ボタンの長押し(右クリック)で固定した、盤面全体でハイライトする数字。
+    num_highlighted: Option<Number>,
+    last_placed: Option<Flash<Square>>,
+    units_completed: Option<Flash<Vec<Square>>>,
+    /// 9 個目を置き終えた数字。
+    number_completed: Option<Flash<Number>>,
+    /// シングルの自動入力で埋めたマス。
+    assisted: Option<Flash<Vec<Square>>>,
+    /// 入力も含めた盤面がもう解けない(`Settings::warn_unsolvable` が有効なときだけ求める)。
+    unsolvable: bool,
+    /// ルール違反なしモードで、置けない数字をはねつけたマス。
+    rejected: Option<Flash<Square>>,
+    /// 矢印キーで盤面の端より先へ動こうとした(盤面を揺らす)。
+    bumped: Option<Flash<()>>,
+    /// ダブルクリックで正誤を確かめたマスと、その結果。
+    checked: Option<Flash<(Square, bool)>>,
+    miss_count: u32,
+    /// 一度でもミスしたマス。
+    missed_squares: Vec<Square>,
+    hint_count: u32,
+    /// この問題で使えるヒントの残り回数。`None` なら無制限。
+    hints_remaining: Option<u32>,
+    /// シングルの自動入力で埋めたマスの数。
+    assist_count: u32,
+    state: State,
+    /// タブが隠れたことで自動的に一時停止した。タブが戻ったら再開する。
+    auto_paused: bool,
+    daily: Option<Daily>,
+    /// 生成した問題の情報。貼り付けや保存データから始めたゲームでは `None`。
+    meta: Option<PuzzleMeta>,
+    /// 貼り付けられ、読み込みの確認待ちの問題。
+    pending_import: Option<Board>,
+    /// ギブアップの確認待ち。
+    give_up_armed: bool,
+    /// 操作の失敗などを伝えるメッセージ。
+    notice: Option<Flash<String>>,
+    /// クリアした問題の記録(古い順)。
+    history: Vec<CompletedRecord>,
+    /// 名前を付けて保存したゲーム(保存した順)。
+    slots: Vec<SaveSlot>,
+    /// 保存するスロットの名前の入力欄。
+    slot_name: String,
+    settings: Settings,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        let sudoku = Game::new(Sudoku::new(Board::empty()));
+
+        Self {
+            sudoku,
+            sq_selected: None,
+            selection: vec![],
+            sq_hover: None,
+            touch: None,
+            sq_missed: None,
+            last_miss: None,
+            repeated_miss: None,
+            num_highlighted: None,
+            last_placed: None,
+            units_completed: None,
+            number_completed: None,
+            assisted: None,
+            unsolvable: false,
+            rejected: None,
+            bumped: None,
+            checked: None,
+            miss_count: 0,
+            missed_squares: vec![],
+            hint_count: 0,
+            hints_remaining: None,
+            assist_count: 0,
+            state: State::Startup,
+            auto_paused: false,
+            daily: None,
+            meta: None,
+            pending_import: None,
+            give_up_armed: false,
+            notice: None,
+            history: vec![],
+            slots: vec![],
+            slot_name: String::new(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl Model {
+    fn solution_at(&self, sq: Square) -> Number {
+        self.sudoku.solution().unwrap()[sq].unwrap()
+    }
+
+    fn is_zen(&self) -> bool {
+        self.settings.game_mode == GameMode::Zen
+    }
+
+    /// プレイ中なら一時停止する。経過時間はそこで止まる。
+    fn pause(&mut self) {
+        if let State::Playing { limit, .. } = self.state {
+            self.state = State::Paused {
+                elapsed: self.state.elapsed().unwrap_or_default(),
+                limit,
+            };
+        }
+    }
+
+    /// 一時停止中なら再開する。一時停止していた時間は経過時間に含めない。
+    fn resume(&mut self) {
+        if let State::Paused { elapsed, limit } = self.state {
+            self.state = State::Playing {
+                now: Instant::now() - elapsed,
+                limit,
+            };
+        }
+        self.auto_paused = false;
+    }
+
+    /// 開始待ちならタイマーを動かし始める。
+    fn start_timer(&mut self) {
+        if let State::Ready { limit } = self.state {
+            self.state = self.playing_or_countdown(limit);
+        }
+    }
+
+    /// 計測を始めるときの状態。カウントダウンの設定があればまずカウントダウンする。
+    fn playing_or_countdown(&self, limit: Option<Duration>) -> State {
+        if self.settings.countdown {
+            State::Countdown {
+                remaining: COUNTDOWN_TICKS,
+                limit,
+            }
+        } else {
+            State::Playing {
+                now: Instant::now(),
+                limit,
+            }
+        }
+    }
+
+    /// マス `sq` でのミスを、ミスの数え方の設定に従って数える。練習モードでは数えない。
+    fn count_miss(&mut self, sq: Square) {
+        let is_new_square = !self.missed_squares.contains(&sq);
+        if is_new_square {
+            self.missed_squares.push(sq);
+        }
+        let counts = match self.settings.miss_mode {
+            MissMode::PerAttempt => true,
+            MissMode::PerSquare => is_new_square,
+        };
+        if !self.settings.practice && counts {
+            self.miss_count += 1;
+        }
+    }
+
+    /// `sq` だけを選択する。
+    fn select(&mut self, sq: Square) {
+        self.sq_selected = Some(sq);
+        self.selection = vec![sq];
+    }
+
+    /// 盤面を変えた後に呼び、もう解けなくなったかを求め直す。設定が無効なら常に `false`。
+    fn check_solvable(&mut self) {
+        self.unsolvable = self.settings.warn_unsolvable && !self.sudoku.is_still_solvable();
+    }
+
+    /// 置けると確かめた数字を置き、完成判定などを行う。
+    ///
+    /// 呼び出し側で、通常モードなら解と一致すること、解と照合しないモードならルール上置けることを
+    /// 確かめておく。後者では解と違う数字も置かれうる。
+    fn place(&mut self, sq: Square, num: Number) {
+        let outcome = match self.sudoku.place_and_report(sq, num) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                log!(format!(
+                    "internal error: placing a checked number failed: {e} (board: {})",
+                    self.sudoku.board_string()
+                ));
+                return;
+            }
+        };
+        self.check_solvable();
+        if self.settings.auto_remove_notes {
+            self.sudoku.remove_note_from_peers(sq, num);
+        }
+        self.sq_missed = None;
+        self.repeated_miss = None;
+        self.last_placed = Some(Flash::new(sq));
+        let completed_squares = outcome.completed_squares();
+        if !completed_squares.is_empty() {
+            self.units_completed = Some(Flash::new(completed_squares));
+        }
+        if outcome.number_completed {
+            self.number_completed = Some(Flash::new(num));
+        }
+        if outcome.solved {
+            let State::Playing { limit, .. } = self.state else {
+                return;
+            };
+            let dur = self.state.elapsed().unwrap_or_default();
+            let remaining = limit.map(|limit| limit.saturating_sub(dur));
+            if self.is_zen() {
+                self.state = State::Completed {
+                    dur,
+                    remaining,
+                    score: None,
+                };
+                return;
+            }
+            let difficulty = self.sudoku.rate();
+            let score = score(difficulty, dur, self.miss_count, self.hint_count);
+            let best = save_high_score(difficulty, score);
+            self.state = State::Completed {
+                dur,
+                remaining,
+                score: Some((score, best)),
+            };
+            if let Some(daily) = &mut self.daily {
+                daily.save_record(dur);
+            }
+            self.history.push(CompletedRecord {
+                difficulty,
+                secs: dur.as_secs(),
+                miss_count: self.miss_count,
+                hint_count: self.hint_count,
+            });
+            CompletedRecord::save_all(&mut self.history);
+        }
+    }
+
+    fn start_game(&mut self, sudoku: Sudoku, solution: Board) {
+        self.sudoku = Game::with_solution(sudoku, solution);
+        self.sq_selected = None;
+        self.selection.clear();
+        self.sq_missed = None;
+        self.repeated_miss = None;
+        self.last_placed = None;
+        self.units_completed = None;
+        self.number_completed = None;
+        self.assisted = None;
+        self.unsolvable = false;
+        self.rejected = None;
+        self.checked = None;
+        self.miss_count = 0;
+        self.missed_squares.clear();
+        self.hint_count = 0;
+        self.hints_remaining = self.settings.hint_limit;
+        self.assist_count = 0;
+        self.give_up_armed = false;
+        let limit = self
+            .settings
+            .time_limit
+            .filter(|_| !self.is_zen())
+            .map(Duration::from_secs);
+        self.state = if self.settings.start_on_first_move {
+            State::Ready { limit }
+        } else {
+            self.playing_or_countdown(limit)
+        };
+        self.daily = None;
+        self.meta = None;
+    }
+}
+
+/// マス `sq` へのタッチの開始。`y` は画面上の縦位置、`at` は開始時刻。
+#[derive(Debug)]
+struct Touch {
+    sq: Square,
+    y: i32,
+    at: Instant,
+}
+
+/// ミスした入力。`explained` はプレイヤーが解説を求めたかどうか。
+#[derive(Debug)]
+struct Miss {
+    sq: Square,
+    entered: Number,
+    correct: Number,
+    explained: bool,
+}
+
+/// 難易度ごとのハイスコアを更新し、更新後のハイスコアを返す。
+fn save_high_score(difficulty: Difficulty, score: u32) -> u32 {
+    let key = format!("high-score-{difficulty:?}");
+    let best = LocalStorage::get(&key).map_or(score, |best: u32| best.max(score));
+    if LocalStorage::insert(&key, &best).is_err() {
+        log!("failed to save high score");
+    }
+    best
+}
+
+/// クリアした問題 1 つ分の記録。
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CompletedRecord {
+    difficulty: Difficulty,
+    secs: u64,
+    miss_count: u32,
+    hint_count: u32,
+}
+
+impl CompletedRecord {
+    const STORAGE_KEY: &'static str = "history";
+    /// localStorage に残す記録の数。超えた分は古いものから捨てる。
+    const MAX_RECORDS: usize = 100;
+
+    fn load_all() -> Vec<Self> {
+        LocalStorage::get(Self::STORAGE_KEY).unwrap_or_default()
+    }
+
+    fn save_all(records: &mut Vec<Self>) {
+        if records.len() > Self::MAX_RECORDS {
+            records.drain(..records.len() - Self::MAX_RECORDS);
+        }
+        if LocalStorage::insert(Self::STORAGE_KEY, records).is_err() {
+            log!("failed to save history");
+        }
+    }
+}
+
+/// 名前を付けて保存したゲーム。
+///
+/// 盤面は `Game::to_save_string` の文字列で持つので、盤面の形式の互換性はそちらで保たれる。
+/// 後から足したフィールドには `#[serde(default)]` を付け、古い保存データも読めるようにする。
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+struct SaveSlot {
+    /// 保存形式の版。形式を変えたら `SaveSlot::VERSION` を上げ、`load_all` で古い版を読み替える。
+    /// 版 0 (版のフィールドがなかったころ)と版 1 にはヒントの残り回数と制限時間がなく、
+    /// 版 2 以前には遊び方がない。
+    #[serde(default)]
+    version: u32,
+    name: String,
+    /// 保存した日時(表示用)。
+    saved_at: String,
+    difficulty: Difficulty,
+    /// `Game::to_save_string` の出力。
+    game: String,
+    /// 解盤面(`BoardExt::to_line`)。
+    solution: String,
+    elapsed_secs: u64,
+    miss_count: u32,
+    hint_count: u32,
+    /// ヒントの残り回数。`None` なら無制限。
+    #[serde(default)]
+    hints_remaining: Option<u32>,
+    /// タイムアタックの制限時間(秒)。`None` なら制限なし。
+    #[serde(default)]
+    limit_secs: Option<u64>,
+    /// 保存したときの遊び方。読み込むと設定もこれに切り替わる。
+    #[serde(default)]
+    game_mode: GameMode,
+}
+
+impl SaveSlot {
+    const STORAGE_KEY: &'static str = "slots";
+    const VERSION: u32 = 3;
+
+    /// 保存したスロットをすべて読む。この版より新しい形式のスロットは読めないので飛ばす。
+    ///
+    /// 古い版のスロットは、足りないフィールドを既定値(ヒント無制限、制限時間なし、通常モード)として読む。
+    fn load_all() -> Vec<Self> {
+        let slots: Vec<Self> = LocalStorage::get(Self::STORAGE_KEY).unwrap_or_default();
+        slots
+            .into_iter()
+            .filter(|slot| {
+                let known = slot.version <= Self::VERSION;
+                if !known {
+                    log!(format!(
+                        "skipping slot {:?} of unknown version {}",
+                        slot.name, slot.version
+                    ));
+                }
+                known
+            })
+            .collect()
+    }
+
+    fn save_all(slots: &[Self]) {
+        if LocalStorage::insert(Self::STORAGE_KEY, slots).is_err() {
+            log!("failed to save slots");
+        }
+    }
+
+    /// 保存したゲームを解とともに読み戻す。
+    fn restore(&self) -> Option<Game> {
+        let mut game = Game::from_save_string(&self.game).ok()?;
+        game.set_solution(Board::from_line(&self.solution).ok()?);
+        Some(game)
+    }
+}
+
+/// 現在の日時を `2026-10-14 09:30` 形式にする。
+fn now_text() -> String {
+    let now = js_sys::Date::new_0();
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        now.get_full_year(),
+        now.get_month() + 1,
+        now.get_date(),
+        now.get_hours(),
+        now.get_minutes()
+    )
+}
+
+/// 今日の問題。
+#[derive(Debug)]
+struct Daily {
+    /// `20261014` 形式の日付。
+    date: u64,
+    /// この日付のクリアタイム(秒)。localStorage に保存される。
+    record: Option<u64>,
+}
+
+impl Daily {
+    fn today() -> Self {
+        let now = js_sys::Date::new_0();
+        let date = u64::from(now.get_full_year()) * 10000
+            + u64::from(now.get_month() + 1) * 100
+            + u64::from(now.get_date());
+        let record = LocalStorage::get(Self::storage_key(date)).ok();
+
+        Self { date, record }
+    }
+
+    fn storage_key(date: u64) -> String {
+        format!("daily-{date}")
+    }
+
+    fn save_record(&mut self, dur: Duration) {
+        let secs = dur.as_secs();
+        if self.record.is_some_and(|record| record <= secs) {
+            return;
+        }
+        self.record = Some(secs);
+        if LocalStorage::insert(Self::storage_key(self.date), &secs).is_err() {
+            log!("failed to save daily record");
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+struct Settings {
+    /// 正しく 9 個置き終えた数字ボタンを押せなくする。
+    disable_completed_numbers: bool,
+    /// 選択中の空マスに候補数字を薄く表示する。
+    show_selected_candidates: bool,
+    /// 空マスの隅に候補の個数を小さく表示する。
+    show_candidate_counts: bool,
+    /// 練習モード: 数字ボタンにカーソルを乗せると正誤がわかり、ミスを数えない。
+    practice: bool,
+    numbers_position: NumbersPosition,
+    /// 推測なしで解ける問題だけを出す。
+    logical_only: bool,
+    /// 数字を置いたとき、同じ行/列/ブロックのメモからその数字を自動で消す。
+    auto_remove_notes: bool,
+    /// タイムアタックの制限時間(秒)。`None` なら通常の経過時間表示。
+    time_limit: Option<u64>,
+    /// 1 問で使えるヒントの回数。`None` なら無制限。
+    hint_limit: Option<u32>,
+    palette: Palette,
+    glyphs: Glyphs,
+    miss_mode: MissMode,
+    game_mode: GameMode,
+    /// タブが隠れている間は自動的に一時停止する。
+    auto_pause: bool,
+    /// 最初にマスを選ぶか数字を置くまでタイマーを止めておく。
+    start_on_first_move: bool,
+    /// タイマーを始める前に 3-2-1 のカウントダウンをする。
+    countdown: bool,
+    /// 入力のせいで盤面がもう解けなくなったら知らせる。ネタばれになりうるので既定では無効。
+    warn_unsolvable: bool,
+    /// 選択中の空マスに置ける数字だけをボタンとして表示する。
+    candidate_chips: bool,
+    /// 時間・ミス・ヒントなどを 1 行にまとめて表示する。
+    show_hud: bool,
+    /// 数字を選んでいるとき、その数字を置けない空マスに斜線の影を付ける。
+    shade_blocked_squares: bool,
+    /// ブロックの境界も太線にせず、すべて細線で描く。
+    uniform_borders: bool,
+    /// マスを上下にスワイプして数字を切り替え、長押しで消す。
+    touch_gestures: bool,
+    /// 盤面と数字ボタンの拡大率(%)。
+    zoom: u32,
+    /// 強調表示の色の凡例を表示する。
+    show_legend: bool,
+    /// 矢印キーで盤面の端から反対の端へ回り込む。無効なら端で止まる。
+    wrap_arrow_keys: bool,
+    /// 新しい問題のヒント数。使うときは `Settings::clues` で範囲内に収める。
+    clues: usize,
+}
+
+impl Settings {
+    const STORAGE_KEY: &'static str = "settings";
+
+    const ZOOM_MIN: u32 = 50;
+    const ZOOM_MAX: u32 = 200;
+    const ZOOM_STEP: u32 = 10;
+
+    /// ヒント数のスライダーの範囲。17 未満では唯一解の問題が存在せず、多すぎても簡単すぎる。
+    const CLUES_MIN: usize = 17;
+    const CLUES_MAX: usize = 60;
+
+    fn load() -> Self {
+        LocalStorage::get(Self::STORAGE_KEY).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if LocalStorage::insert(Self::STORAGE_KEY, self).is_err() {
+            log!("failed to save settings");
+        }
+    }
+
+    /// マスや数字ボタンの大きさに掛ける倍率。
+    fn scale(&self) -> f64 {
+        f64::from(self.zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX)) / 100.0
+    }
+
+    /// 新しい問題のヒント数。保存された設定が範囲外でもスライダーの範囲に収める。
+    fn clues(&self) -> usize {
+        self.clues.clamp(Self::CLUES_MIN, Self::CLUES_MAX)
+    }
+}
+
+/// 遊び方。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+enum GameMode {
+    #[default]
+    Normal,
+    /// 禅モード: タイマー・ミス・スコアなしで、ルール上置ける数字なら何でも置ける。
+    Zen,
+    /// 初心者向け: 解とは照合せず、ルール上置けない数字だけを(ミスとして数えずに)はねつける。
+    NoIllegal,
+    /// 仮置き: ルール上置けない数字だけをミスとして数えてはねつける。解と違っても置ける数字は
+    /// 仮の数字として置けるが、それを直さない限り完成しない。
+    Tentative,
+}
+
+/// ミスの数え方。
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+enum MissMode {
+    /// 誤った数字を入れるたびに数える。
+    PerAttempt,
+    /// 各マスで最初の 1 回だけ数える。
+    PerSquare,
+}
+
+/// 強調表示の配色。
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+enum Palette {
+    Default,
+    /// 赤と緑を使わない、色覚多様性に配慮した配色(Okabe-Ito)。
+    Deuteranopia,
+}
+
+/// 数字の表示に使う文字。中身の扱いは常に数字で、見た目だけが変わる。
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+enum Glyphs {
+    /// `1`-`9`。
+    Digits,
+    /// `A`-`I`。
+    Letters,
+}
+
+impl Glyphs {
+    fn render(self, num: Number) -> String {
+        match self {
+            Self::Digits => num.get().to_string(),
+            Self::Letters => char::from(b'A' + num.get() - 1).to_string(),
+        }
+    }
+}
+
+/// 数字ボタンを盤面のどちら側に置くか。
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+enum NumbersPosition {
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            disable_completed_numbers: true,
+            show_selected_candidates: false,
+            show_candidate_counts: false,
+            practice: false,
+            numbers_position: NumbersPosition::Bottom,
+            logical_only: false,
+            auto_remove_notes: true,
+            time_limit: None,
+            hint_limit: None,
+            palette: Palette::Default,
+            glyphs: Glyphs::Digits,
+            miss_mode: MissMode::PerAttempt,
+            game_mode: GameMode::Normal,
+            auto_pause: true,
+            start_on_first_move: false,
+            countdown: false,
+            warn_unsolvable: false,
+            candidate_chips: false,
+            show_hud: false,
+            uniform_borders: false,
+            touch_gestures: false,
+            shade_blocked_squares: false,
+            zoom: 100,
+            show_legend: false,
+            wrap_arrow_keys: false,
+            clues: 35,
+        }
+    }
+}
+
+/// 数 tick だけ表示する一時的なハイライト。
+#[derive(Debug)]
+struct Flash<T> {
+    value: T,
+    ticks: u32,
+}
+
+impl<T> Flash<T> {
+    const TICKS: u32 = 10;
+
+    fn new(value: T) -> Self {
+        Self::with_ticks(value, Self::TICKS)
+    }
+
+    fn with_ticks(value: T, ticks: u32) -> Self {
+        Self { value, ticks }
+    }
+}
+
+fn tick_flash<T>(flash: &mut Option<Flash<T>>) {
+    if let Some(f) = flash {
+        f.ticks = f.ticks.saturating_sub(1);
+        if f.ticks == 0 {
+            *flash = None;
+        }
+    }
+}
+
+/// カウントダウンの長さ(`TimerTick` 数)。3 秒。
+const COUNTDOWN_TICKS: u32 = 30;
+
+/// お知らせ(`Model::notice`)を表示しておく長さ(`TimerTick` 数)。5 秒。
+const NOTICE_TICKS: u32 = 50;
+
+#[derive(Debug)]
+enum State {
+    Startup,
+    /// 最初の操作を待っている(タイマーは止まっている)。`limit` はタイムアタックの制限時間。
+    Ready {
+        limit: Option<Duration>,
+    },
+    /// 開始前のカウントダウン中。`remaining` は残りの `TimerTick` 数。
+    Countdown {
+        remaining: u32,
+        limit: Option<Duration>,
+    },
+    /// `limit` はタイムアタックの制限時間。
+    Playing {
+        now: Instant,
+        limit: Option<Duration>,
+    },
+    /// 一時停止中。`elapsed` は一時停止までの経過時間。
+    Paused {
+        elapsed: Duration,
+        limit: Option<Duration>,
+    },
+    /// `remaining` はタイムアタックの残り時間、`score` は (スコア, この難易度のハイスコア)。
+    /// 禅モードではスコアを付けない。
+    Completed {
+        dur: Duration,
+        remaining: Option<Duration>,
+        score: Option<(u32, u32)>,
+    },
+    /// タイムアタックで時間切れになった。
+    Failed,
+    /// ギブアップして解を表示した。
+    Revealed,
+}
+
+impl State {
+    /// 現在までの経過時間。一時停止していた時間は含まない。
+    ///
+    /// 開始前(`Ready`/`Countdown`)は 0、完成後はクリアタイム。時間の意味がない状態では `None`。
+    /// 経過時間はすべてここから読む。
+    fn elapsed(&self) -> Option<Duration> {
+        match *self {
+            Self::Ready { .. } | Self::Countdown { .. } => Some(Duration::ZERO),
+            Self::Playing { now, .. } => Some(now.elapsed()),
+            Self::Paused { elapsed, .. } => Some(elapsed),
+            Self::Completed { dur, .. } => Some(dur),
+            Self::Startup | Self::Failed | Self::Revealed => None,
+        }
+    }
+
+    /// タイムアタックの制限時間。完成後や制限のない状態では `None`。
+    fn limit(&self) -> Option<Duration> {
+        match *self {
+            Self::Ready { limit }
+            | Self::Countdown { limit, .. }
+            | Self::Playing { limit, .. }
+            | Self::Paused { limit, .. } => limit,
+            Self::Startup | Self::Completed { .. } | Self::Failed | Self::Revealed => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Msg {
+    TimerTick,
+    Reset,
+    Daily,
+    /// テクニック `Technique` の練習問題を作り始める。生成は遅いので、作成中の通知を出してから
+    /// `GenerateDrill` で行う。
+    Drill(Technique),
+    /// テクニック `Technique` の練習問題を作って始める(`SudokuExt::generate_drill`)。
+    GenerateDrill(Technique),
+    ClearEntries,
+    SelectSquare(Square),
+    ExtendSelection(Square),
+    ClearSelection,
+    HoverSquare(Option<Square>),
+    TouchStart {
+        sq: Square,
+        y: i32,
+    },
+    TouchEnd {
+        y: i32,
+    },
+    ClearSquare(Square),
+    ToggleConfirmed(Square),
+    ToggleStruck {
+        sq: Square,
+        num: Number,
+    },
+    TogglePause,
+    /// タブの表示状態が変わった。`true` なら隠れた。
+    VisibilityChange(bool),
+    CheckSquare(Square),
+    PutNumber {
+        sq: Square,
+        num: Number,
+    },
+    Hint,
+    FillSingles,
+    ToggleDigitNotes(Number),
+    ToggleSelectionNotes(Number),
+    ExplainMiss,
+    Paste(String),
+    KeyDown(String),
+    /// 選択中のマスから `drow` 行下、`dcol` 列右へ動く。
+    MoveSelection {
+        drow: isize,
+        dcol: isize,
+    },
+    /// 選択中のマスの次(`reverse` なら前)の空マスを選ぶ(`BoardExt::next_empty_square`)。
+    SelectNextEmpty {
+        reverse: bool,
+    },
+    ToggleNumberHighlight(Number),
+    ConfirmImport,
+    CancelImport,
+    GiveUp,
+    ConfirmGiveUp,
+    CancelGiveUp,
+    DismissNotice,
+    SlotNameInput(String),
+    SaveSlot,
+    LoadSlot(usize),
+    DeleteSlot(usize),
+    ToggleHud,
+    ToggleLegend,
+    ResetMisses,
+    ChangeSettings(Settings),
+}
+
+fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    orders.send_msg(Msg::Reset);
+    orders.stream(streams::interval(100, || Msg::TimerTick));
+    orders.stream(streams::window_event(Ev::Paste, |event| {
+        if is_typing_target(&event) {
+            return None;
+        }
+        let event: web_sys::ClipboardEvent = event.unchecked_into();
+        let text = event.clipboard_data()?.get_data("text").ok()?;
+        Some(Msg::Paste(text))
+    }));
+    orders.stream(streams::window_event(Ev::KeyDown, |event| {
+        if is_typing_target(&event) {
+            return None;
+        }
+        let event: web_sys::KeyboardEvent = event.unchecked_into();
+        // Tab はフォーカス移動に使わせず、空マスの間の移動に使う。
+        if event.key() == "Tab" {
+            event.prevent_default();
+            return Some(Msg::SelectNextEmpty {
+                reverse: event.shift_key(),
+            });
+        }
+        Some(Msg::KeyDown(event.key()))
+    }));
+    orders.stream(streams::document_event(Ev::VisibilityChange, |_| {
+        Msg::VisibilityChange(document().hidden())
+    }));
+
+    Model {
+        history: CompletedRecord::load_all(),
+        slots: SaveSlot::load_all(),
+        settings: Settings::load(),
+        ..Model::default()
+    }
+}
+
+/// 入力欄でのキー入力や貼り付けはショートカットや盤面の読み込みとして扱わない。
+fn is_typing_target(event: &web_sys::Event) -> bool {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|el| matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"))
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    if !matches!(msg, Msg::TimerTick | Msg::HoverSquare(_) | Msg::ExplainMiss) {
+        model.last_miss = None;
+    }
+    if matches!(
+        msg,
+        Msg::SelectSquare(_) | Msg::PutNumber { .. } | Msg::Hint | Msg::FillSingles
+    ) {
+        model.start_timer();
+    }
+
+    match msg {
+        Msg::TimerTick => {
+            tick_flash(&mut model.last_placed);
+            tick_flash(&mut model.units_completed);
+            tick_flash(&mut model.number_completed);
+            tick_flash(&mut model.assisted);
+            tick_flash(&mut model.rejected);
+            tick_flash(&mut model.bumped);
+            tick_flash(&mut model.checked);
+            tick_flash(&mut model.notice);
+            if let State::Countdown { remaining, limit } = &mut model.state {
+                *remaining = remaining.saturating_sub(1);
+                if *remaining == 0 {
+                    model.state = State::Playing {
+                        now: Instant::now(),
+                        limit: *limit,
+                    };
+                }
+            }
+            if let State::Playing {
+                limit: Some(limit), ..
+            } = model.state
+            {
+                if model
+                    .state
+                    .elapsed()
+                    .is_some_and(|elapsed| elapsed >= limit)
+                {
+                    model.state = State::Failed;
+                }
+            }
+        }
+        Msg::Reset => {
+            if model.settings.logical_only {
+                let clues = model.settings.clues();
+                let Some((sudoku, solution)) = Sudoku::generate_logical(clues, Difficulty::Hard)
+                else {
+                    let text = "推測なしで解ける問題を作れませんでした".to_owned();
+                    model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+                    return;
+                };
+                model.start_game(sudoku, Board::from_solution(&solution));
+            } else {
+                match Sudoku::try_generate_unique(model.settings.clues()) {
+                    Ok((sudoku, solution, meta)) => {
+                        model.start_game(sudoku, Board::from_solution(&solution));
+                        model.meta = Some(meta);
+                    }
+                    Err(e) => {
+                        let text = format!("問題を作れませんでした: {e}");
+                        model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+                    }
+                }
+            }
+        }
+        Msg::Daily => {
+            let daily = Daily::today();
+            let (sudoku, solution) = Sudoku::daily(daily.date);
+            let meta = PuzzleMeta::new(daily.date, sudoku.board());
+            model.start_game(sudoku, Board::from_solution(&solution));
+            model.daily = Some(daily);
+            model.meta = Some(meta);
+        }
+        Msg::Drill(technique) => {
+            let text = format!("{} の練習問題を作っています…", technique.label());
+            model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+            // 通知が描画されてから生成を始める。
+            orders.perform_cmd(cmds::timeout(0, move || Msg::GenerateDrill(technique)));
+        }
+        Msg::GenerateDrill(technique) => {
+            model.notice = None;
+            let Some((sudoku, solution)) = Sudoku::generate_drill(technique) else {
+                let text = "練習問題を作れませんでした".to_owned();
+                model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+                return;
+            };
+            model.start_game(sudoku, Board::from_solution(&solution));
+        }
+        Msg::ClearEntries => {
+            if matches!(model.state, State::Revealed) {
+                return;
+            }
+            model.sudoku.clear_entries();
+            model.sq_missed = None;
+            model.last_placed = None;
+            model.check_solvable();
+        }
+        Msg::SelectSquare(sq) => {
+            if model.repeated_miss.is_some_and(|(s, _, _)| s != sq) {
+                model.repeated_miss = None;
+            }
+            model.select(sq);
+            model.last_placed = None;
+        }
+        Msg::ExtendSelection(sq) => {
+            if let Some(i) = model.selection.iter().position(|&s| s == sq) {
+                model.selection.remove(i);
+                model.sq_selected = model.selection.last().copied();
+            } else {
+                model.selection.push(sq);
+                model.sq_selected = Some(sq);
+            }
+            model.last_placed = None;
+        }
+        Msg::ClearSelection => {
+            model.sq_selected = None;
+            model.selection.clear();
+        }
+        Msg::CheckSquare(sq) => {
+            let Some(num) = model.sudoku.board()[sq] else {
+                return;
+            };
+            if model.sudoku.is_given(sq) {
+                return;
+            }
+            model.checked = Some(Flash::new((sq, num == model.solution_at(sq))));
+        }
+        Msg::TogglePause => {
+            if matches!(model.state, State::Paused { .. }) {
+                model.resume();
+            } else {
+                model.pause();
+            }
+        }
+        Msg::VisibilityChange(hidden) => {
+            if !model.settings.auto_pause {
+                return;
+            }
+            if hidden && matches!(model.state, State::Playing { .. }) {
+                model.pause();
+                model.auto_paused = true;
+            } else if !hidden && model.auto_paused {
+                model.resume();
+            }
+        }
+        Msg::ToggleConfirmed(sq) => {
+            model.sudoku.toggle_confirmed(sq);
+        }
+        Msg::ToggleStruck { sq, num } => {
+            let _ = model.sudoku.apply_move(Move::Strike { sq, num });
+        }
+        Msg::HoverSquare(sq) => {
+            model.sq_hover = sq;
+        }
+        Msg::TouchStart { sq, y } => {
+            model.touch = Some(Touch {
+                sq,
+                y,
+                at: Instant::now(),
+            });
+        }
+        Msg::TouchEnd { y } => {
+            const SWIPE_PX: i32 = 30;
+            const LONG_PRESS: Duration = Duration::from_millis(500);
+
+            let Some(touch) = model.touch.take() else {
+                return;
+            };
+            let sq = touch.sq;
+            let dy = y - touch.y;
+            if dy.abs() >= SWIPE_PX {
+                // 通常モードでは解と違う数字がミスとして数えられたうえ置かれないので、
+                // 候補を順に切り替えられない。スワイプは解と照合しないモードでだけ使う。
+                if model.settings.game_mode == GameMode::Normal {
+                    return;
+                }
+                // 入っている数字を除いた盤面での候補を順に切り替える。上へのスワイプで大きい数字へ。
+                // ルール上置ける数字だけなので、どのモードでもミスにはならない。
+                let mut board = model.sudoku.board().clone();
+                let current = board[sq].take();
+                if let Some(num) = board.candidates(sq).cycle(current, dy < 0) {
+                    orders.send_msg(Msg::PutNumber { sq, num });
+                }
+            } else if touch.at.elapsed() >= LONG_PRESS {
+                orders.send_msg(Msg::ClearSquare(sq));
+            }
+        }
+        Msg::ClearSquare(sq) => {
+            if matches!(
+                model.state,
+                State::Countdown { .. } | State::Failed | State::Paused { .. } | State::Revealed
+            ) {
+                return;
+            }
+            if model.sudoku.apply_move(Move::Clear { sq }).is_ok() && model.sq_missed == Some(sq) {
+                model.sq_missed = None;
+            }
+            model.check_solvable();
+        }
+        Msg::ToggleSelectionNotes(num) => {
+            model.sudoku.toggle_notes(&model.selection, num);
+        }
+        Msg::PutNumber { sq, num } => {
+            if matches!(
+                model.state,
+                State::Countdown { .. } | State::Failed | State::Paused { .. } | State::Revealed
+            ) || model.sudoku.is_given(sq)
+                || model.sudoku.is_confirmed(sq)
+            {
+                return;
+            }
+            // 禅モード・ルール違反なしモード・仮置きモードでは解と照合せず、ルール上置ける数字なら
+            // そのまま置く。
+            match model.settings.game_mode {
+                GameMode::Normal => {}
+                GameMode::Zen | GameMode::NoIllegal | GameMode::Tentative => {
+                    if model.sudoku.board().can_put(sq, num) {
+                        model.place(sq, num);
+                    } else if model.settings.game_mode != GameMode::Zen {
+                        model.rejected = Some(Flash::new(sq));
+                        if model.settings.game_mode == GameMode::Tentative {
+                            model.count_miss(sq);
+                        }
+                    }
+                    return;
+                }
+            }
+            if num != model.solution_at(sq) {
+                model.sq_missed = Some(sq);
+                model.last_miss = Some(Miss {
+                    sq,
+                    entered: num,
+                    correct: model.solution_at(sq),
+                    explained: false,
+                });
+                let count = match model.repeated_miss {
+                    Some((s, n, count)) if s == sq && n == num => count + 1,
+                    _ => 1,
+                };
+                model.repeated_miss = Some((sq, num, count));
+                model.count_miss(sq);
+                return;
+            }
+            model.place(sq, num);
+        }
+        Msg::Hint => {
+            if !matches!(model.state, State::Playing { .. }) || model.hints_remaining == Some(0) {
+                return;
+            }
+            // 自由入力やゼンモードでは間違った(が重複はしない)入力が残っていることがあるので、
+            // 問題数字と正しい入力だけの盤面から考える。
+            let board = model.sudoku.board();
+            let mut known = model.sudoku.givens().clone();
+            for (sq, num) in board.iter() {
+                if num == Some(model.solution_at(sq)) {
+                    known[sq] = num;
+                }
+            }
+            let can_place = |sq: Square, num: Number| {
+                board[sq].is_none() && board.can_put(sq, num) && num == model.solution_at(sq)
+            };
+            // 論理的に確定できるマスがなければ、置ける最初の空マスの答えを教える。
+            let placement = techniques::next_placement(&known)
+                .map(|(sq, num, _)| (sq, num))
+                .filter(|&(sq, num)| can_place(sq, num))
+                .or_else(|| {
+                    Square::all()
+                        .into_iter()
+                        .map(|sq| (sq, model.solution_at(sq)))
+                        .find(|&(sq, num)| can_place(sq, num))
+                });
+            let Some((sq, num)) = placement else {
+                return;
+            };
+            model.hint_count += 1;
+            if let Some(remaining) = &mut model.hints_remaining {
+                *remaining -= 1;
+            }
+            model.select(sq);
+            model.place(sq, num);
+        }
+        Msg::FillSingles => {
+            if !matches!(model.state, State::Playing { .. }) {
+                return;
+            }
+            let mut filled = vec![];
+            loop {
+                let grid = Grid::new(model.sudoku.board());
+                let placement = techniques::naked_single(&grid)
+                    .or_else(|| techniques::hidden_single(&grid))
+                    .and_then(|step| step.placement);
+                let Some((sq, num)) = placement else {
+                    break;
+                };
+                // 入力はすべて正しいので起こらないはずだが、念のため解と食い違ったら止める。
+                if num != model.solution_at(sq) {
+                    break;
+                }
+                model.place(sq, num);
+                filled.push(sq);
+            }
+            if !filled.is_empty() {
+                model.assist_count += filled.len() as u32;
+                model.assisted = Some(Flash::new(filled));
+            }
+        }
+        Msg::ToggleDigitNotes(num) => {
+            model.sudoku.toggle_digit_notes(num);
+        }
+        Msg::ExplainMiss => {
+            if let Some(miss) = &mut model.last_miss {
+                miss.explained = true;
+            }
+        }
+        Msg::Paste(text) => {
+            let board = match Board::parse_grid(&text) {
+                Ok(board) => board,
+                Err(e) => {
+                    let text = format!("盤面として読み込めません ({e})");
+                    model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+                    return;
+                }
+            };
+            let problem = match solver::classify(&board) {
+                Classification::Unique => {
+                    model.pending_import = Some(board);
+                    return;
+                }
+                Classification::Unsolvable => "解がありません",
+                Classification::Multiple => "解が複数あります",
+            };
+            let text = format!("この問題は読み込めません: {problem}");
+            model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+        }
+        Msg::ConfirmImport => {
+            let Some(board) = model.pending_import.take() else {
+                return;
+            };
+            let solution = solver::solve(&board).unwrap();
+            model.start_game(Sudoku::new(board), solution);
+        }
+        Msg::CancelImport => {
+            model.pending_import = None;
+        }
+        Msg::GiveUp => {
+            if matches!(
+                model.state,
+                State::Ready { .. } | State::Playing { .. } | State::Paused { .. }
+            ) {
+                model.give_up_armed = true;
+            }
+        }
+        Msg::ConfirmGiveUp => {
+            if !std::mem::take(&mut model.give_up_armed) {
+                return;
+            }
+            // 誤った入力も含めて消してから解を埋めるので、put が失敗することはない。
+            model.sudoku.clear_entries();
+            for sq in Square::all() {
+                if model.sudoku.board()[sq].is_none() {
+                    let num = model.solution_at(sq);
+                    let _ = model.sudoku.apply_move(Move::Place { sq, num });
+                }
+            }
+            model.sq_missed = None;
+            model.last_placed = None;
+            model.auto_paused = false;
+            model.unsolvable = false;
+            model.state = State::Revealed;
+        }
+        Msg::CancelGiveUp => {
+            model.give_up_armed = false;
+        }
+        Msg::DismissNotice => {
+            model.notice = None;
+        }
+        Msg::KeyDown(key) => match key.as_str() {
+            "h" | "H" => {
+                orders.send_msg(Msg::Hint);
+            }
+            "Escape" => {
+                orders.send_msg(Msg::ClearSelection);
+            }
+            "Backspace" | "Delete" => {
+                if let Some(sq) = model.sq_selected {
+                    orders.send_msg(Msg::ClearSquare(sq));
+                }
+            }
+            "ArrowUp" => {
+                orders.send_msg(Msg::MoveSelection { drow: -1, dcol: 0 });
+            }
+            "ArrowDown" => {
+                orders.send_msg(Msg::MoveSelection { drow: 1, dcol: 0 });
+            }
+            "ArrowLeft" => {
+                orders.send_msg(Msg::MoveSelection { drow: 0, dcol: -1 });
+            }
+            "ArrowRight" => {
+                orders.send_msg(Msg::MoveSelection { drow: 0, dcol: 1 });
+            }
+            _ => {}
+        },
+        Msg::MoveSelection { drow, dcol } => {
+            // 何も選んでいなければ左上から始める。
+            let Some(sq) = model.sq_selected else {
+                orders.send_msg(Msg::SelectSquare(Square::all()[0]));
+                return;
+            };
+            let next = if model.settings.wrap_arrow_keys {
+                Some(sq.offset_wrapping(drow, dcol))
+            } else {
+                sq.offset(drow, dcol)
+            };
+            match next {
+                Some(next) => {
+                    orders.send_msg(Msg::SelectSquare(next));
+                }
+                None => model.bumped = Some(Flash::new(())),
+            }
+        }
+        Msg::SelectNextEmpty { reverse } => {
+            let board = model.sudoku.board();
+            // 何も選んでいなければ、最初(`reverse` なら最後)の空マスを選ぶ。
+            let next = match model.sq_selected {
+                Some(sq) => board.next_empty_square(sq, reverse),
+                None => {
+                    let mut empties = board
+                        .iter()
+                        .filter(|(_, cell)| cell.is_none())
+                        .map(|(sq, _)| sq);
+                    if reverse {
+                        empties.last()
+                    } else {
+                        empties.next()
+                    }
+                }
+            };
+            if let Some(sq) = next {
+                orders.send_msg(Msg::SelectSquare(sq));
+            }
+        }
+        Msg::ToggleNumberHighlight(num) => {
+            model.num_highlighted = if model.num_highlighted == Some(num) {
+                None
+            } else {
+                Some(num)
+            };
+        }
+        Msg::SlotNameInput(name) => {
+            model.slot_name = name;
+        }
+        Msg::SaveSlot => {
+            if !matches!(
+                model.state,
+                State::Ready { .. } | State::Playing { .. } | State::Paused { .. }
+            ) {
+                return;
+            }
+            let Some(elapsed) = model.state.elapsed() else {
+                return;
+            };
+            let Some(solution) = model.sudoku.solution() else {
+                return;
+            };
+            let name = match model.slot_name.trim() {
+                "" => format!("スロット {}", model.slots.len() + 1),
+                name => name.to_owned(),
+            };
+            let slot = SaveSlot {
+                version: SaveSlot::VERSION,
+                name,
+                saved_at: now_text(),
+                difficulty: model.sudoku.rate(),
+                game: model.sudoku.to_save_string(),
+                solution: solution.to_line(),
+                elapsed_secs: elapsed.as_secs(),
+                miss_count: model.miss_count,
+                hint_count: model.hint_count,
+                hints_remaining: model.hints_remaining,
+                limit_secs: model.state.limit().map(|limit| limit.as_secs()),
+                game_mode: model.settings.game_mode,
+            };
+            // 同じ名前のスロットがあれば上書きする。
+            match model.slots.iter_mut().find(|s| s.name == slot.name) {
+                Some(s) => *s = slot,
+                None => model.slots.push(slot),
+            }
+            SaveSlot::save_all(&model.slots);
+            model.slot_name.clear();
+        }
+        Msg::LoadSlot(i) => {
+            let Some(slot) = model.slots.get(i) else {
+                return;
+            };
+            let Some(game) = slot.restore() else {
+                let text = format!("{} は読み込めません", slot.name);
+                model.notice = Some(Flash::with_ticks(text, NOTICE_TICKS));
+                return;
+            };
+            let (elapsed, miss_count, hint_count, hints_remaining) = (
+                Duration::from_secs(slot.elapsed_secs),
+                slot.miss_count,
+                slot.hint_count,
+                slot.hints_remaining,
+            );
+            let limit = slot.limit_secs.map(Duration::from_secs);
+            if model.settings.game_mode != slot.game_mode {
+                model.settings.game_mode = slot.game_mode;
+                model.settings.save();
+            }
+            let (givens, solution) = (game.givens().clone(), game.solution().unwrap().clone());
+            model.start_game(Sudoku::new(givens), solution);
+            model.sudoku = game;
+            model.check_solvable();
+            model.miss_count = miss_count;
+            model.hint_count = hint_count;
+            model.hints_remaining = hints_remaining;
+            // 読み込んだ直後は一時停止しておき、再開ボタンで続きから始める。
+            model.state = State::Paused { elapsed, limit };
+        }
+        Msg::DeleteSlot(i) => {
+            if i < model.slots.len() {
+                model.slots.remove(i);
+                SaveSlot::save_all(&model.slots);
+            }
+        }
+        Msg::ResetMisses => {
+            model.miss_count = 0;
+            model.missed_squares.clear();
+            model.sq_missed = None;
+            model.repeated_miss = None;
+        }
+        Msg::ToggleHud => {
+            model.settings.show_hud ^= true;
+            model.settings.save();
+        }
+        Msg::ToggleLegend => {
+            model.settings.show_legend ^= true;
+            model.settings.save();
+        }
+        Msg::ChangeSettings(settings) => {
+            settings.save();
+            model.settings = settings;
+            model.check_solvable();
+        }
+    }
+}
+
+const SQUARE_WIDTH: u32 = 100;
+const SQUARE_HEIGHT: u32 = 100;
+
+const NUMBER_WIDTH: u32 = 100;
+const NUMBER_HEIGHT: u32 = 100;
+
+fn view(model: &Model) -> Node<Msg> {
+    let palette = match model.settings.palette {
+        Palette::Default => None,
+        Palette::Deuteranopia => Some("palette-deuteranopia"),
+    };
+
+    div![
+        id!("app-container"),
+        C![palette],
+        view_sudoku(model),
+        view_control(model),
+        view_import_confirm(model),
+        view_give_up_confirm(model),
+        view_countdown(model),
+    ]
+}
+
+fn view_countdown(model: &Model) -> Node<Msg> {
+    let State::Countdown { remaining, .. } = model.state else {
+        return empty![];
+    };
+
+    // 残り 30-21 tick で 3、20-11 で 2、10-1 で 1。
+    let count = remaining.div_ceil(10);
+
+    div![C!["countdown"], count.to_string()]
+}
+
+fn view_give_up_confirm(model: &Model) -> Node<Msg> {
+    if !model.give_up_armed {
+        return empty![];
+    }
+
+    div![
+        C!["give-up-confirm"],
+        div!["ギブアップして解を表示しますか?"],
+        div![
+            button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "ギブアップ",
+                ev(Ev::Click, |_| Msg::ConfirmGiveUp)
+            ],
+            button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "キャンセル",
+                ev(Ev::Click, |_| Msg::CancelGiveUp)
+            ],
+        ],
+    ]
+}
+
+fn view_import_confirm(model: &Model) -> Node<Msg> {
+    let Some(board) = &model.pending_import else {
+        return empty![];
+    };
+
+    let grid = Row::all()
+        .into_iter()
+        .map(|row| {
+            row_squares(row)
+                .into_iter()
+                .map(|sq| board[sq].map_or(".".to_owned(), |num| num.get().to_string()))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    div![
+        C!["import-confirm"],
+        div!["この問題を読み込みますか?"],
+        pre![C!["import-preview"], grid],
+        div![
+            button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "読み込む",
+                ev(Ev::Click, |_| Msg::ConfirmImport)
+            ],
+            button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "キャンセル",
+                ev(Ev::Click, |_| Msg::CancelImport)
+            ],
+        ],
+    ]
+}
+
+fn view_control(model: &Model) -> Node<Msg> {
+    div![
+        id!("control-container"),
+        view_control_notice(model),
+        view_control_candidate_chips(model),
+        view_control_hud_toggle(model),
+        if model.settings.show_hud {
+            vec![view_control_hud(model)]
+        } else {
+            vec![
+                view_control_difficulty(model),
+                view_control_timer(model),
+                view_control_miss_count(model),
+            ]
+        },
+        view_control_unsolvable(model),
+        view_control_miss_explain(model),
+        view_control_hint_prompt(model),
+        IF!(!model.settings.show_hud => vec![
+            view_control_hint_count(model),
+            view_control_assist_count(model),
+        ]),
+        view_control_reset(model),
+        view_control_daily(model),
+        view_control_drill(),
+        view_control_complete(model),
+        view_legend(model),
+        view_history(model),
+        view_slots(model),
+        view_settings(model),
+    ]
+}
+
+fn view_slots(model: &Model) -> Node<Msg> {
+    let rows = model.slots.iter().enumerate().map(|(i, slot)| {
+        tr![
+            td![&slot.name],
+            td![&slot.saved_at],
+            td![format!("{:?}", slot.difficulty)],
+            td![button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "読み込む",
+                ev(Ev::Click, move |_| Msg::LoadSlot(i))
+            ]],
+            td![button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "削除",
+                ev(Ev::Click, move |_| Msg::DeleteSlot(i))
+            ]],
+        ]
+    });
+
+    details![
+        C!["slots"],
+        summary![format!("Save ({})", model.slots.len())],
+        div![
+            input![
+                attrs! {
+                    At::Type => "text",
+                    At::Placeholder => "名前",
+                    At::Value => &model.slot_name,
+                },
+                input_ev(Ev::Input, Msg::SlotNameInput)
+            ],
+            button![
+                attrs! {
+                    At::Type => "button",
+                },
+                "保存",
+                ev(Ev::Click, |_| Msg::SaveSlot)
+            ],
+        ],
+        table![rows],
+    ]
 }
 
-impl Default for Model {
-    fn default() -> Self {
-        let sudoku = Sudoku::new(Board::empty());
+fn view_history(model: &Model) -> Node<Msg> {
+    let rows = model.history.iter().rev().map(|record| {
+        tr![
+            td![format!("{:?}", record.difficulty)],
+            td![format_duration(Duration::from_secs(record.secs))],
+            td![format!("Miss: {}", record.miss_count)],
+            td![format!("Hint: {}", record.hint_count)],
+        ]
+    });
 
-        Self {
-            sudoku,
-            solution: Board::empty(),
-            sq_selected: None,
-            sq_missed: None,
-            miss_count: 0,
-            state: State::Startup,
-        }
-    }
+    details![
+        C!["history"],
+        summary![format!("History ({})", model.history.len())],
+        table![rows],
+    ]
 }
 
-impl Model {
-    fn solution_at(&self, sq: Square) -> Number {
-        self.solution[sq].unwrap()
-    }
+fn view_settings(model: &Model) -> Node<Msg> {
+    div![
+        C!["settings"],
+        view_setting_toggle(model, "完成した数字を押せなくする", |s| {
+            &mut s.disable_completed_numbers
+        }),
+        view_setting_toggle(model, "選択マスの候補を表示", |s| {
+            &mut s.show_selected_candidates
+        }),
+        view_setting_toggle(model, "候補の個数を表示", |s| {
+            &mut s.show_candidate_counts
+        }),
+        view_setting_toggle(model, "練習モード", |s| &mut s.practice),
+        view_setting_toggle(model, "推測なしで解ける問題", |s| &mut s
+            .logical_only),
+        view_setting_toggle(
+            model,
+            "数字を置いたら周りのメモを消す",
+            |s| &mut s.auto_remove_notes
+        ),
+        view_setting_toggle(model, "置ける数字だけを表示", |s| &mut s
+            .candidate_chips),
+        view_setting_toggle(
+            model,
+            "選んだ数字を置けないマスに影を付ける",
+            |s| &mut s.shade_blocked_squares
+        ),
+        view_setting_toggle(model, "ブロックの境界を太線にしない", |s| {
+            &mut s.uniform_borders
+        }),
+        view_setting_toggle(model, "スワイプで数字を切り替える", |s| &mut s
+            .touch_gestures),
+        view_setting_toggle(model, "最初の操作でタイマー開始", |s| &mut s
+            .start_on_first_move),
+        view_setting_toggle(model, "開始前にカウントダウン", |s| &mut s
+            .countdown),
+        view_setting_toggle(model, "解けなくなったら知らせる", |s| &mut s
+            .warn_unsolvable),
+        view_setting_toggle(model, "矢印キーで端から回り込む", |s| {
+            &mut s.wrap_arrow_keys
+        }),
+        view_setting_toggle(model, "タブを離れたら一時停止", |s| &mut s
+            .auto_pause),
+        view_setting_select(
+            model,
+            "数字の位置",
+            &[
+                (NumbersPosition::Bottom, "下"),
+                (NumbersPosition::Left, "左"),
+                (NumbersPosition::Right, "右"),
+            ],
+            |s| &mut s.numbers_position,
+        ),
+        view_setting_select(
+            model,
+            "タイムアタック",
+            &[
+                (None, "なし"),
+                (Some(5 * 60), "5 分"),
+                (Some(10 * 60), "10 分"),
+                (Some(20 * 60), "20 分"),
+            ],
+            |s| &mut s.time_limit,
+        ),
+        view_setting_select(
+            model,
+            "ヒントの回数",
+            &[
+                (None, "無制限"),
+                (Some(0), "なし"),
+                (Some(1), "1 回"),
+                (Some(3), "3 回"),
+                (Some(5), "5 回"),
+            ],
+            |s| &mut s.hint_limit,
+        ),
+        view_setting_clues(model),
+        view_setting_select(
+            model,
+            "モード",
+            &[
+                (GameMode::Normal, "通常"),
+                (GameMode::Zen, "禅"),
+                (GameMode::NoIllegal, "ルール違反なし"),
+                (GameMode::Tentative, "仮置き"),
+            ],
+            |s| &mut s.game_mode,
+        ),
+        view_setting_select(
+            model,
+            "ミスの数え方",
+            &[
+                (MissMode::PerAttempt, "毎回"),
+                (MissMode::PerSquare, "マスごとに 1 回"),
+            ],
+            |s| &mut s.miss_mode,
+        ),
+        view_setting_select(
+            model,
+            "配色",
+            &[
+                (Palette::Default, "標準"),
+                (Palette::Deuteranopia, "色覚配慮"),
+            ],
+            |s| &mut s.palette,
+        ),
+        view_setting_select(
+            model,
+            "表示する文字",
+            &[
+                (Glyphs::Digits, "数字"),
+                (Glyphs::Letters, "アルファベット")
+            ],
+            |s| &mut s.glyphs,
+        ),
+        view_setting_zoom(model),
+    ]
 }
 
-#[derive(Debug)]
-enum State {
-    Startup,
-    Playing { now: Instant },
-    Completed { dur: Duration },
+fn view_setting_zoom(model: &Model) -> Node<Msg> {
+    let zoom = model.settings.zoom;
+    let zoomed = |zoom: u32| {
+        let mut settings = model.settings.clone();
+        settings.zoom = zoom;
+        Msg::ChangeSettings(settings)
+    };
+    let zoom_out = zoomed(
+        zoom.saturating_sub(Settings::ZOOM_STEP)
+            .max(Settings::ZOOM_MIN),
+    );
+    let zoom_in = zoomed((zoom + Settings::ZOOM_STEP).min(Settings::ZOOM_MAX));
+
+    div![
+        C!["setting", "setting-zoom"],
+        "大きさ",
+        button![
+            attrs! { At::Disabled => (zoom <= Settings::ZOOM_MIN).as_at_value() },
+            "-",
+            ev(Ev::Click, move |_| zoom_out)
+        ],
+        format!("{zoom}%"),
+        button![
+            attrs! { At::Disabled => (zoom >= Settings::ZOOM_MAX).as_at_value() },
+            "+",
+            ev(Ev::Click, move |_| zoom_in)
+        ],
+    ]
 }
 
-#[derive(Debug)]
-enum Msg {
-    TimerTick,
-    Reset,
-    SelectSquare(Square),
-    PutNumber { sq: Square, num: Number },
+fn view_setting_clues(model: &Model) -> Node<Msg> {
+    let clues = model.settings.clues();
+    let mut settings = model.settings.clone();
+
+    label![
+        C!["setting", "setting-clues"],
+        "ヒント数",
+        input![
+            attrs! {
+                At::Type => "range",
+                At::Min => Settings::CLUES_MIN,
+                At::Max => Settings::CLUES_MAX,
+                At::Value => clues,
+            },
+            input_ev(Ev::Change, move |value| {
+                let clues: usize = value.parse().ok()?;
+                settings.clues = clues.clamp(Settings::CLUES_MIN, Settings::CLUES_MAX);
+                Some(Msg::ChangeSettings(settings))
+            })
+        ],
+        clues.to_string(),
+    ]
 }
 
-fn init(_url: Url, orders: &mut impl Orders<Msg>) -> Model {
-    orders.send_msg(Msg::Reset);
-    orders.stream(streams::interval(100, || Msg::TimerTick));
+fn view_setting_select<T: Copy + PartialEq + 'static>(
+    model: &Model,
+    text: &str,
+    options: &'static [(T, &'static str)],
+    field: fn(&mut Settings) -> &mut T,
+) -> Node<Msg> {
+    let mut settings = model.settings.clone();
+    let current = *field(&mut settings);
 
-    Model::default()
+    let opts = options.iter().enumerate().map(|(i, &(value, label))| {
+        option![
+            attrs! {
+                At::Value => i,
+                At::Selected => (value == current).as_at_value(),
+            },
+            label
+        ]
+    });
+
+    label![
+        C!["setting"],
+        text,
+        select![
+            opts,
+            input_ev(Ev::Change, move |value| {
+                let i: usize = value.parse().ok()?;
+                *field(&mut settings) = options.get(i)?.0;
+                Some(Msg::ChangeSettings(settings))
+            })
+        ],
+    ]
 }
 
-fn update(msg: Msg, model: &mut Model, _orders: &mut impl Orders<Msg>) {
-    match msg {
-        Msg::TimerTick => {}
-        Msg::Reset => {
-            let (sudoku, solution) = Sudoku::generate_unique(35);
-            model.sudoku = sudoku;
-            model.solution = solution.board().clone();
-            model.sq_selected = None;
-            model.sq_missed = None;
-            model.miss_count = 0;
-            model.state = State::Playing {
-                now: Instant::now(),
-            };
-        }
-        Msg::SelectSquare(sq) => {
-            model.sq_selected = Some(sq);
-        }
-        Msg::PutNumber { sq, num } => {
-            if num != model.solution_at(sq) {
-                model.sq_missed = Some(sq);
-                model.miss_count += 1;
-                return;
-            }
-            if !model.sudoku.put(sq, num) {
-                log!("internal error: sudoku.put() should succeed");
-            }
-            model.sq_missed = None;
-            if model.sudoku.is_solved() {
-                let State::Playing{now} = model.state else {
-                    return;
-                };
-                let dur = now.elapsed();
-                model.state = State::Completed { dur };
-            }
-        }
-    }
+fn view_setting_toggle(
+    model: &Model,
+    text: &str,
+    field: fn(&mut Settings) -> &mut bool,
+) -> Node<Msg> {
+    let mut settings = model.settings.clone();
+    let value = field(&mut settings);
+    let checked = *value;
+    *value = !checked;
+
+    label![
+        C!["setting"],
+        input![
+            attrs! {
+                At::Type => "checkbox",
+                At::Checked => checked.as_at_value(),
+            },
+            ev(Ev::Change, move |_| Msg::ChangeSettings(settings))
+        ],
+        text,
+    ]
 }
 
-const SQUARE_WIDTH: u32 = 100;
-const SQUARE_HEIGHT: u32 = 100;
+fn view_control_notice(model: &Model) -> Node<Msg> {
+    let Some(notice) = &model.notice else {
+        return empty![];
+    };
 
-const NUMBER_WIDTH: u32 = 100;
-const NUMBER_HEIGHT: u32 = 100;
+    div![
+        C!["output-notice"],
+        notice.value.as_str(),
+        ev(Ev::Click, |_| Msg::DismissNotice)
+    ]
+}
+
+fn view_control_candidate_chips(model: &Model) -> Node<Msg> {
+    let Some(sq) = model.sq_selected else {
+        return empty![];
+    };
+    if !model.settings.candidate_chips || model.sudoku.board()[sq].is_some() {
+        return empty![];
+    }
+
+    let chips = model
+        .sudoku
+        .board()
+        .candidate_list(sq)
+        .into_iter()
+        .map(|num| {
+            button![
+                C!["button-candidate"],
+                attrs! {
+                    At::Type => "button",
+                },
+                model.settings.glyphs.render(num),
+                ev(Ev::Click, move |_| Msg::PutNumber { sq, num })
+            ]
+        });
+
+    div![C!["candidate-chips"], chips]
+}
+
+fn view_control_reset(model: &Model) -> Node<Msg> {
+    let num_highlighted = model.num_highlighted;
+    // 消せる入力のあるマスを選んでいるときだけ押せる。
+    let sq_clearable = model.sq_selected.filter(|&sq| {
+        model.sudoku.board()[sq].is_some()
+            && !model.sudoku.is_given(sq)
+            && !model.sudoku.is_confirmed(sq)
+    });
+    let notes_text = num_highlighted.map_or("数字をメモ".to_owned(), |num| {
+        format!("{} をメモ", num.get())
+    });
+    let hint_text = model
+        .hints_remaining
+        .map_or("ヒント (H)".to_owned(), |remaining| {
+            format!("ヒント (H) 残り {remaining}")
+        });
+    let pause_text = if matches!(model.state, State::Paused { .. }) {
+        "再開"
+    } else {
+        "一時停止"
+    };
 
-fn view(model: &Model) -> Node<Msg> {
     div![
-        id!("app-container"),
-        view_sudoku(model),
-        view_control(model)
+        button![
+            C!["button-reset"],
+            attrs! {
+                At::Type => "button",
+            },
+            "リセット",
+            ev(Ev::Click, |_| Msg::Reset)
+        ],
+        button![
+            C!["button-clear"],
+            attrs! {
+                At::Type => "button",
+            },
+            "入力を消去",
+            ev(Ev::Click, |_| Msg::ClearEntries)
+        ],
+        button![
+            C!["button-clear-square"],
+            attrs! {
+                At::Type => "button",
+                At::Disabled => sq_clearable.is_none().as_at_value(),
+            },
+            "マスを消す (Del)",
+            ev(Ev::Click, move |_| sq_clearable.map(Msg::ClearSquare))
+        ],
+        button![
+            C!["button-pause"],
+            attrs! {
+                At::Type => "button",
+            },
+            pause_text,
+            ev(Ev::Click, |_| Msg::TogglePause)
+        ],
+        button![
+            C!["button-hint"],
+            attrs! {
+                At::Type => "button",
+                At::Disabled => (model.hints_remaining == Some(0)).as_at_value(),
+            },
+            hint_text,
+            ev(Ev::Click, |_| Msg::Hint)
+        ],
+        button![
+            C!["button-fill-singles"],
+            attrs! {
+                At::Type => "button",
+            },
+            "シングルを埋める",
+            ev(Ev::Click, |_| Msg::FillSingles)
+        ],
+        button![
+            C!["button-give-up"],
+            attrs! {
+                At::Type => "button",
+            },
+            "ギブアップ",
+            ev(Ev::Click, |_| Msg::GiveUp)
+        ],
+        button![
+            C!["button-notes"],
+            attrs! {
+                At::Type => "button",
+                At::Disabled => num_highlighted.is_none().as_at_value(),
+            },
+            notes_text,
+            ev(Ev::Click, move |_| num_highlighted
+                .map(Msg::ToggleDigitNotes))
+        ],
     ]
 }
 
-fn view_control(model: &Model) -> Node<Msg> {
+fn view_control_daily(model: &Model) -> Node<Msg> {
+    let text = model.daily.as_ref().map_or("".to_owned(), |daily| {
+        let (y, m, d) = (daily.date / 10000, daily.date / 100 % 100, daily.date % 100);
+        let record = daily.record.map_or("".to_owned(), |secs| {
+            format!(" (Best: {})", format_duration(Duration::from_secs(secs)))
+        });
+        format!("Daily: {y}-{m:02}-{d:02}{record}")
+    });
+
     div![
-        id!("control-container"),
-        view_control_timer(model),
-        view_control_miss_count(model),
-        view_control_reset(model),
-        view_control_complete(model),
+        button![
+            C!["button-daily"],
+            attrs! {
+                At::Type => "button",
+            },
+            "今日の問題",
+            ev(Ev::Click, |_| Msg::Daily)
+        ],
+        div![C!["output-daily"], text],
     ]
 }
 
-fn view_control_reset(_model: &Model) -> Node<Msg> {
-    div![button![
-        C!["button-reset"],
+fn view_control_drill() -> Node<Msg> {
+    let buttons = Technique::ALL.into_iter().map(|technique| {
+        button![
+            attrs! {
+                At::Type => "button",
+            },
+            technique.label(),
+            ev(Ev::Click, move |_| Msg::Drill(technique))
+        ]
+    });
+
+    div![C!["drill"], "テクニックの練習: ", buttons]
+}
+
+fn format_duration(dur: Duration) -> String {
+    let secs = dur.as_secs();
+    let mins = secs / 60;
+    let secs = secs % 60;
+    format!("{mins:02}:{secs:02}")
+}
+
+fn view_control_hud_toggle(model: &Model) -> Node<Msg> {
+    let text = if model.settings.show_hud {
+        "詳しく表示"
+    } else {
+        "まとめて表示"
+    };
+
+    button![
+        C!["button-hud"],
         attrs! {
             At::Type => "button",
         },
-        "リセット",
-        ev(Ev::Click, |_| Msg::Reset)
-    ]]
+        text,
+        ev(Ev::Click, |_| Msg::ToggleHud)
+    ]
+}
+
+/// 強調表示の凡例。`(マスのクラス, 見本の文字, 説明)`。
+const LEGEND: [(&str, &str, &str); 6] = [
+    ("square-selected", "5", "選択中のマス"),
+    ("square-neighbor", "5", "同じ行/列/ブロック"),
+    ("square-selected-number", "5", "同じ数字"),
+    ("square-missed", "☓", "ミス"),
+    ("square-rejected", "", "ルール上置けない"),
+    ("square-given", "5", "問題数字"),
+];
+
+/// 強調表示の凡例。見本には盤面のマスと同じクラスを付けるので、配色の設定にも追従する。
+fn view_legend(model: &Model) -> Node<Msg> {
+    let text = if model.settings.show_legend {
+        "凡例を隠す"
+    } else {
+        "凡例を表示"
+    };
+    let items: Vec<_> = LEGEND
+        .into_iter()
+        .map(|(class, sample, label)| {
+            div![
+                C!["legend-item"],
+                div![C!["legend-swatch", class], sample],
+                label
+            ]
+        })
+        .collect();
+
+    div![
+        C!["legend"],
+        button![
+            attrs! {
+                At::Type => "button",
+            },
+            text,
+            ev(Ev::Click, |_| Msg::ToggleLegend)
+        ],
+        IF!(model.settings.show_legend => items),
+    ]
+}
+
+/// 難易度・時間・ミス・ヒント・残りマス数を 1 行にまとめた表示。
+fn view_control_hud(model: &Model) -> Node<Msg> {
+    let remaining = model
+        .sudoku
+        .board()
+        .iter()
+        .filter(|&(_, num)| num.is_none())
+        .count();
+    let (text_dur, is_warning) = timer_text(model);
+
+    div![
+        C!["output-hud"],
+        span![format!("{:?}", model.sudoku.rate())],
+        IF!(!model.is_zen() => span![
+            C![IF!(is_warning => "output-time-warning")],
+            text_dur
+        ]),
+        IF!(!model.is_zen() => span![format!("Miss {}", model.miss_count)]),
+        span![format!("Hint {}", model.hint_count)],
+        span![format!("残り {remaining}")],
+    ]
+}
+
+/// タイマーに表示する時間と、残り時間が少ないかどうか。
+fn timer_text(model: &Model) -> (String, bool) {
+    const WARNING_SECS: u64 = 30;
+
+    let elapsed = model.state.elapsed().unwrap_or(Duration::ZERO);
+    match model.state {
+        State::Playing { limit: None, .. } => (format_duration(elapsed), false),
+        State::Playing {
+            limit: Some(limit), ..
+        } => {
+            let remaining = limit.saturating_sub(elapsed);
+            (
+                format_duration(remaining),
+                remaining.as_secs() < WARNING_SECS,
+            )
+        }
+        State::Completed {
+            remaining: None, ..
+        } => (format_duration(elapsed), false),
+        State::Completed {
+            remaining: Some(remaining),
+            ..
+        } => (format_duration(remaining), false),
+        State::Ready { limit } | State::Countdown { limit, .. } => {
+            (format_duration(limit.unwrap_or(Duration::ZERO)), false)
+        }
+        State::Paused { limit: None, .. } => (format_duration(elapsed), false),
+        State::Paused {
+            limit: Some(limit), ..
+        } => (format_duration(limit.saturating_sub(elapsed)), false),
+        State::Failed => (format_duration(Duration::ZERO), true),
+        State::Revealed => ("--:--".to_owned(), false),
+        State::Startup => ("".to_owned(), false),
+    }
 }
 
 fn view_control_timer(model: &Model) -> Node<Msg> {
-    fn format_duration(dur: Duration) -> String {
-        let secs = dur.as_secs();
-        let mins = secs / 60;
-        let secs = secs % 60;
-        format!("{mins:02}:{secs:02}")
+    if model.is_zen() {
+        return empty![];
     }
 
-    let text_dur = match model.state {
-        State::Playing { now } => format_duration(now.elapsed()),
-        State::Completed { dur } => format_duration(dur),
-        _ => "".to_owned(),
-    };
+    let (text_dur, is_warning) = timer_text(model);
     let text = format!("Time: {text_dur}");
+    let ready_hint = if matches!(model.state, State::Ready { .. }) {
+        div![C!["output-ready"], "マスを選ぶとスタート"]
+    } else {
+        empty![]
+    };
+
+    div![
+        div![
+            C!["output-time", IF!(is_warning => "output-time-warning")],
+            text
+        ],
+        ready_hint
+    ]
+}
+
+fn view_control_difficulty(model: &Model) -> Node<Msg> {
+    let text = match &model.meta {
+        Some(meta) => format!(
+            "Level: {:?} (Seed: {}, {} clues)",
+            meta.difficulty, meta.seed, meta.clue_count
+        ),
+        None => format!("Level: {:?}", model.sudoku.rate()),
+    };
 
-    div![C!["output-time"], text]
+    div![C!["output-difficulty"], text]
 }
 
 fn view_control_miss_count(model: &Model) -> Node<Msg> {
+    if model.is_zen() {
+        return empty![];
+    }
+
     let text = format!("Miss: {}", model.miss_count);
 
-    div![C!["output-miss"], text]
+    div![
+        C!["output-miss"],
+        text,
+        button![
+            C!["button-reset-misses"],
+            attrs! {
+                At::Type => "button",
+            },
+            "ミスを 0 に戻す",
+            ev(Ev::Click, |_| Msg::ResetMisses)
+        ],
+    ]
 }
 
-fn view_control_complete(model: &Model) -> Node<Msg> {
-    let text = if matches!(model.state, State::Completed { .. }) {
-        "Complete!!"
+fn view_control_unsolvable(model: &Model) -> Node<Msg> {
+    if !model.unsolvable {
+        return empty![];
+    }
+
+    div![C!["output-unsolvable"], "この盤面はもう解けません"]
+}
+
+fn view_control_miss_explain(model: &Model) -> Node<Msg> {
+    let Some(miss) = &model.last_miss else {
+        return empty![];
+    };
+
+    if !miss.explained {
+        return div![
+            C!["output-miss-explain"],
+            a![
+                C!["link-explain"],
+                "なぜ?",
+                ev(Ev::Click, |_| Msg::ExplainMiss)
+            ]
+        ];
+    }
+
+    let conflicts = model.sudoku.board().conflicts(miss.sq, miss.entered);
+    let reason = if conflicts.is_empty() {
+        format!("正解は {} です", miss.correct.get())
     } else {
-        ""
+        let squares: Vec<_> = conflicts.iter().map(|sq| sq.label()).collect();
+        format!(
+            "{} に {} があります (正解は {})",
+            squares.join(", "),
+            miss.entered.get(),
+            miss.correct.get()
+        )
+    };
+    let text = format!(
+        "{} に {} は入りません: {reason}",
+        miss.sq.label(),
+        miss.entered.get()
+    );
+
+    div![C!["output-miss-explain"], text]
+}
+
+/// 同じ誤りを `REPEAT` 回繰り返したらヒントを勧める。
+fn view_control_hint_prompt(model: &Model) -> Node<Msg> {
+    const REPEAT: u32 = 3;
+
+    if !model
+        .repeated_miss
+        .is_some_and(|(_, _, count)| count >= REPEAT)
+    {
+        return empty![];
+    }
+
+    div![
+        C!["output-hint-prompt"],
+        "行き詰まったらヒントを使ってみましょう ",
+        button![
+            C!["button-hint"],
+            attrs! {
+                At::Type => "button",
+            },
+            "ヒント",
+            ev(Ev::Click, |_| Msg::Hint)
+        ],
+    ]
+}
+
+fn view_control_hint_count(model: &Model) -> Node<Msg> {
+    let text = format!("Hint: {}", model.hint_count);
+
+    div![C!["output-hint"], text]
+}
+
+fn view_control_assist_count(model: &Model) -> Node<Msg> {
+    let text = format!("Assist: {}", model.assist_count);
+
+    div![C!["output-assist"], text]
+}
+
+fn view_control_complete(model: &Model) -> Node<Msg> {
+    if model.is_zen() {
+        let text = match model.state {
+            State::Completed { .. } => "おつかれさまでした",
+            State::Revealed => "解を表示しました",
+            _ => "",
+        };
+        return div![C!["output-complete-zen"], text];
+    }
+
+    let text = match model.state {
+        State::Completed { .. } => "Complete!!",
+        State::Failed => "Time Up",
+        State::Revealed => "Give Up",
+        _ => "",
+    };
+    let score = match model.state {
+        State::Completed {
+            score: Some((score, best)),
+            ..
+        } => div![C!["output-score"], format!("Score: {score} (Best: {best})")],
+        _ => empty![],
     };
 
-    div![C!["output-complete"], text]
+    div![div![C!["output-complete"], text], score]
 }
 
 fn view_sudoku(model: &Model) -> Node<Msg> {
+    let (direction, justify) = match model.settings.numbers_position {
+        NumbersPosition::Bottom => ("column", "space-between"),
+        NumbersPosition::Left => ("row-reverse", "center"),
+        NumbersPosition::Right => ("row", "center"),
+    };
+
     div![
         id!("sudoku-container"),
+        style! {
+            St::FlexDirection => direction,
+            St::JustifyContent => justify,
+        },
         view_board(model),
         view_numbers(model),
     ]
@@ -178,8 +2308,17 @@ fn view_sudoku(model: &Model) -> Node<Msg> {
 
 fn view_board(model: &Model) -> Node<Msg> {
     let rows = Row::all().into_iter().map(|row| view_board_row(model, row));
+    let is_paused = matches!(model.state, State::Paused { .. } | State::Countdown { .. });
 
-    table![id!("board-container"), rows]
+    table![
+        id!("board-container"),
+        C![
+            IF!(is_paused => "board-paused"),
+            IF!(model.settings.touch_gestures => "board-touch"),
+            IF!(model.bumped.is_some() => "board-bumped")
+        ],
+        rows
+    ]
 }
 
 fn view_board_row(model: &Model, row: Row) -> Node<Msg> {
@@ -195,22 +2334,86 @@ fn view_board_square(model: &Model, col: Col, row: Row) -> Node<Msg> {
     let board = model.sudoku.board();
     let sq_sel = model.sq_selected;
 
-    let is_selected = sq_sel == Some(sq);
-    let is_neighbor = !is_selected
-        && sq_sel.map_or(false, |sq_sel| {
-            col == sq_sel.col() || row == sq_sel.row() || sq.block() == sq_sel.block()
-        });
+    let is_selected = model.selection.contains(&sq);
+    let is_neighbor = !is_selected && sq_sel.is_some_and(|sq_sel| sq.shares_unit_with(sq_sel));
     let is_selected_number = sq_sel.map_or(false, |sq_sel| {
         board[sq].is_some() && board[sq] == board[sq_sel]
     });
+    let is_highlighted_number = board[sq].is_some() && board[sq] == model.num_highlighted;
+    let is_blocked = model.settings.shade_blocked_squares
+        && board[sq].is_none()
+        && model
+            .num_highlighted
+            .is_some_and(|num| !board.candidates(sq).contains(num));
     let is_missed = model.sq_missed == Some(sq);
+    let is_hovered = model.sq_hover == Some(sq);
+    let is_given = model.sudoku.is_given(sq);
+    let is_confirmed = model.sudoku.is_confirmed(sq);
+    // 仮置きモードでは、入力は確定印を付けるまで仮の数字として表示する(正誤は見せない)。
+    let is_tentative = model.settings.game_mode == GameMode::Tentative
+        && board[sq].is_some()
+        && !is_given
+        && !is_confirmed;
+    let is_last_placed = model.last_placed.as_ref().map(|f| f.value) == Some(sq);
+    let is_rejected = model.rejected.as_ref().map(|f| f.value) == Some(sq);
+    let is_unit_completed = model
+        .units_completed
+        .as_ref()
+        .is_some_and(|f| f.value.contains(&sq));
+    let is_number_completed =
+        board[sq].is_some() && board[sq] == model.number_completed.as_ref().map(|f| f.value);
+    let checked = model
+        .checked
+        .as_ref()
+        .filter(|f| f.value.0 == sq)
+        .map(|f| f.value.1);
+    let is_assisted = model
+        .assisted
+        .as_ref()
+        .is_some_and(|f| f.value.contains(&sq));
+
+    let show_candidates = is_selected
+        && model.selection.len() == 1
+        && !is_missed
+        && board[sq].is_none()
+        && model.settings.show_selected_candidates;
 
-    let text = if is_missed {
-        "☓".to_owned()
+    let notes = model.sudoku.notes(sq);
+    let struck = model.sudoku.struck(sq);
+
+    let content = if show_candidates {
+        view_pencil_marks(sq, board.candidates(sq), struck, model.settings.glyphs)
+    } else if is_missed {
+        Node::new_text("☓")
+    } else if board[sq].is_none() && !(notes.is_empty() && struck.is_empty()) {
+        view_pencil_marks(sq, notes, struck, model.settings.glyphs)
+    } else {
+        Node::new_text(board[sq].map_or("".to_owned(), |num| model.settings.glyphs.render(num)))
+    };
+    let candidate_count = if model.settings.show_candidate_counts && board[sq].is_none() {
+        div![
+            C!["candidate-count"],
+            board.candidates(sq).len().to_string()
+        ]
+    } else {
+        empty![]
+    };
+    let borders = view_square_borders(sq, model.settings.uniform_borders);
+    let scale = model.settings.scale();
+    let touch_handlers = if model.settings.touch_gestures {
+        vec![
+            touch_ev(Ev::TouchStart, move |event| {
+                let y = event.changed_touches().get(0)?.client_y();
+                Some(Msg::TouchStart { sq, y })
+            }),
+            touch_ev(Ev::TouchEnd, |event| {
+                let y = event.changed_touches().get(0)?.client_y();
+                Some(Msg::TouchEnd { y })
+            }),
+        ]
     } else {
-        board[sq].map_or("".to_owned(), |num| num.get().to_string())
+        vec![]
     };
-    let borders = view_square_borders(sq);
 
     td![
         style! {
@@ -224,26 +2427,81 @@ fn view_board_square(model: &Model, col: Col, row: Row) -> Node<Msg> {
         div![
             C![
                 "square",
+                IF!(is_given => "square-given"),
+                IF!(is_confirmed => "square-confirmed"),
+                IF!(is_tentative => "square-tentative"),
                 IF!(is_selected => "square-selected"),
                 IF!(is_neighbor => "square-neighbor"),
                 IF!(is_selected_number => "square-selected-number"),
+                IF!(is_highlighted_number => "square-highlighted-number"),
+                IF!(is_blocked => "square-blocked"),
                 IF!(is_missed => "square-missed"),
+                IF!(is_hovered => "square-hover"),
+                IF!(is_last_placed => "square-last-placed"),
+                IF!(is_rejected => "square-rejected"),
+                IF!(is_unit_completed => "unit-complete"),
+                IF!(is_number_completed => "number-complete-flash"),
+                IF!(is_assisted => "square-assisted"),
+                IF!(checked == Some(true) => "square-check-correct"),
+                IF!(checked == Some(false) => "square-check-wrong"),
             ],
             style! {
-                St::Width => px(SQUARE_WIDTH),
-                St::Height => px(SQUARE_HEIGHT),
-                St::FontSize => px(f64::from(SQUARE_HEIGHT) * 0.8),
+                St::Width => px(f64::from(SQUARE_WIDTH) * scale),
+                St::Height => px(f64::from(SQUARE_HEIGHT) * scale),
+                St::FontSize => px(f64::from(SQUARE_HEIGHT) * scale * 0.8),
             },
-            text,
-            ev(Ev::Click, move |_| Msg::SelectSquare(sq))
+            content,
+            candidate_count,
+            mouse_ev(Ev::Click, move |event| {
+                if event.alt_key() {
+                    Msg::ToggleConfirmed(sq)
+                } else if event.ctrl_key() || event.meta_key() {
+                    Msg::ExtendSelection(sq)
+                } else {
+                    Msg::SelectSquare(sq)
+                }
+            }),
+            ev(Ev::DblClick, move |_| Msg::CheckSquare(sq)),
+            ev(Ev::MouseEnter, move |_| Msg::HoverSquare(Some(sq))),
+            ev(Ev::MouseLeave, |_| Msg::HoverSquare(None)),
+            touch_handlers
         ]
     ]
 }
 
-fn view_square_borders(sq: Square) -> [String; 4] {
+/// `nums` と、取り消し線を引いた `struck` を 3x3 に並べる。
+///
+/// 表示中の数字を Shift+クリックすると取り消し線を付け外しする。
+fn view_pencil_marks(sq: Square, nums: NumberSet, struck: NumberSet, glyphs: Glyphs) -> Node<Msg> {
+    let marks = Number::all().into_iter().map(|num| {
+        let is_struck = struck.contains(num);
+        if !nums.contains(num) && !is_struck {
+            return div![C!["pencil-mark"]];
+        }
+        div![
+            C!["pencil-mark", IF!(is_struck => "pencil-mark-struck")],
+            glyphs.render(num),
+            mouse_ev(Ev::Click, move |event| {
+                event.shift_key().then(|| {
+                    event.stop_propagation();
+                    Msg::ToggleStruck { sq, num }
+                })
+            }),
+        ]
+    });
+
+    div![C!["pencil-marks"], marks]
+}
+
+/// `uniform` なら 3x3 ブロックの境界も含めてすべて細線にする。
+fn view_square_borders(sq: Square, uniform: bool) -> [String; 4] {
     const THICK: u32 = 8;
     const THIN: u32 = 2;
 
+    if uniform {
+        return [px(THIN), px(THIN), px(THIN), px(THIN)];
+    }
+
     let top = px(if sq.row().get() % 3 == 0 { THICK } else { THIN });
     let right = px(if sq.col().get() % 3 == 2 { THICK } else { THIN });
     let bottom = px(if sq.row().get() % 3 == 2 { THICK } else { THIN });
@@ -255,11 +2513,26 @@ fn view_square_borders(sq: Square) -> [String; 4] {
 fn view_numbers(model: &Model) -> Node<Msg> {
     let numbers = Number::all().into_iter().map(|num| view_number(model, num));
 
+    let scale = model.settings.scale();
+    let (width, height, direction) = match model.settings.numbers_position {
+        NumbersPosition::Bottom => (
+            f64::from(NUMBER_WIDTH) * scale * 9.5,
+            f64::from(NUMBER_HEIGHT) * scale,
+            "row",
+        ),
+        NumbersPosition::Left | NumbersPosition::Right => (
+            f64::from(NUMBER_WIDTH) * scale,
+            f64::from(NUMBER_HEIGHT) * scale * 9.5,
+            "column",
+        ),
+    };
+
     div![
         id!("numbers-container"),
         style! {
-            St::Width => px(f64::from(NUMBER_WIDTH) * 9.5),
-            St::Height => px(NUMBER_HEIGHT),
+            St::Width => px(width),
+            St::Height => px(height),
+            St::FlexDirection => direction,
         },
         numbers,
     ]
@@ -267,27 +2540,119 @@ fn view_numbers(model: &Model) -> Node<Msg> {
 
 fn view_number(model: &Model, num: Number) -> Node<Msg> {
     let sq_sel = model.sq_selected;
-    let is_completed = Square::all()
-        .into_iter()
-        .filter(|&sq| model.sudoku.board()[sq] == Some(num))
+    let is_completed = model
+        .sudoku
+        .board()
+        .iter()
+        .filter(|&(sq, n)| {
+            n == Some(num) && model.sudoku.solution().is_some_and(|s| s[sq] == Some(num))
+        })
         .count()
         == 9;
+    let is_disabled = is_completed && model.settings.disable_completed_numbers;
+    let is_just_completed = model.number_completed.as_ref().map(|f| f.value) == Some(num);
+    let practice_hint = sq_sel
+        .filter(|&sq| model.settings.practice && model.sudoku.board()[sq].is_none())
+        .and_then(|sq| model.sudoku.is_correct_move(sq, num));
 
-    let text = num.get().to_string();
+    let is_highlighted = model.num_highlighted == Some(num);
+    let is_multi_selection = model.selection.len() > 1;
+
+    let text = model.settings.glyphs.render(num);
+    let scale = model.settings.scale();
 
     div![
-        C!["number", IF!(is_completed => "number-completed")],
+        C![
+            "number",
+            IF!(is_completed => "number-completed"),
+            IF!(is_just_completed => "number-complete-flash"),
+            IF!(is_highlighted => "number-highlighted"),
+            IF!(practice_hint == Some(true) => "number-practice-correct"),
+            IF!(practice_hint == Some(false) => "number-practice-wrong"),
+        ],
         style! {
-            St::Width => px(NUMBER_WIDTH),
-            St::Height => px(NUMBER_HEIGHT),
-            St::FontSize => px(f64::from(NUMBER_HEIGHT) * 0.8),
+            St::Width => px(f64::from(NUMBER_WIDTH) * scale),
+            St::Height => px(f64::from(NUMBER_HEIGHT) * scale),
+            St::FontSize => px(f64::from(NUMBER_HEIGHT) * scale * 0.8),
         },
         text,
         ev(Ev::Click, move |_| {
-            if is_completed {
+            if is_multi_selection {
+                return Some(Msg::ToggleSelectionNotes(num));
+            }
+            if is_disabled {
                 return None;
             }
             sq_sel.map(|sq| Msg::PutNumber { sq, num })
+        }),
+        ev(Ev::ContextMenu, move |event| {
+            event.prevent_default();
+            Msg::ToggleNumberHighlight(num)
         })
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn slot() -> SaveSlot {
+        let solution = Board::from_line(SOLUTION).unwrap();
+        let mut game = Game::with_solution(
+            Sudoku::new(Board::parse_flexible(PUZZLE).unwrap()),
+            solution,
+        );
+        game.apply_move(Move::Place {
+            sq: Square::all()[2],
+            num: Number::all()[3],
+        })
+        .unwrap();
+        SaveSlot {
+            version: SaveSlot::VERSION,
+            name: "スロット 1".to_owned(),
+            saved_at: "2026-10-14 09:30".to_owned(),
+            difficulty: Difficulty::Easy,
+            game: game.to_save_string(),
+            solution: SOLUTION.to_owned(),
+            elapsed_secs: 123,
+            miss_count: 2,
+            hint_count: 1,
+            hints_remaining: Some(2),
+            limit_secs: Some(600),
+            game_mode: GameMode::Tentative,
+        }
+    }
+
+    #[test]
+    fn save_slot_round_trip() {
+        let slot = slot();
+        let json = serde_json::to_string(&slot).unwrap();
+        let loaded: SaveSlot = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, slot);
+        assert_eq!(loaded.miss_count, 2);
+        assert_eq!(loaded.hint_count, 1);
+        assert_eq!(loaded.elapsed_secs, 123);
+        assert_eq!(loaded.game_mode, GameMode::Tentative);
+
+        let game = loaded.restore().unwrap();
+        assert_eq!(game, slot.restore().unwrap());
+        assert_eq!(game.solution().unwrap().to_line(), SOLUTION);
+    }
+
+    #[test]
+    fn save_slot_of_version_2_defaults_game_mode() {
+        let mut json = serde_json::to_value(slot()).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("game_mode");
+        fields.insert("version".to_owned(), 2.into());
+        let loaded: SaveSlot = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.version, 2);
+        assert_eq!(loaded.game_mode, GameMode::Normal);
+        assert_eq!(loaded.miss_count, 2);
+    }
+}
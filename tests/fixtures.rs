@@ -0,0 +1,68 @@
+//! ベンチ(`benches/sudoku.rs`)で使う難問について、ソルバーとレーティングが既知の結果と一致することを確かめる。
+//!
+//! ソルバーを速くする変更で結果が変わっていないかを見張る。
+
+use sudoku::*;
+
+use sudoku_app::{
+    board::BoardExt,
+    solver,
+    techniques::{self, Difficulty},
+};
+
+/// `(名前, 問題, 解, techniques::rate の結果)`。`benches/sudoku.rs` の `HARD_PUZZLES` と同じ問題。
+///
+/// - Royle #1, #2: Gordon Royle の 17 ヒント問題集から。ヒントは最少だがシングルだけで解ける。
+/// - AI Escargot: Arto Inkala (2006)。実装済みのテクニックでは解けない。
+/// - Inkala 2012: Arto Inkala が「世界一難しい」として 2012 年に発表したもの。同上。
+const FIXTURES: &[(&str, &str, &str, Difficulty)] = &[
+    (
+        "17-clue (Royle #1)",
+        "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+        "693784512487512936125963874932651487568247391741398625319475268856129743274836159",
+        Difficulty::Easy,
+    ),
+    (
+        "17-clue (Royle #2)",
+        "000000012000035000000600070700000300000400800100000000000120000080000040050000600",
+        "673894512912735486845612973798261354526473891134589267469128735287356149351947628",
+        Difficulty::Easy,
+    ),
+    (
+        "AI Escargot",
+        "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+        "162857493534129678789643521475312986913586742628794135356478219241935867897261354",
+        Difficulty::Expert,
+    ),
+    (
+        "Inkala 2012",
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        "812753649943682175675491283154237896369845721287169534521974368438526917796318452",
+        Difficulty::Expert,
+    ),
+];
+
+#[test]
+fn fixtures_have_known_solutions() {
+    for &(name, s, solution, _) in FIXTURES {
+        let board = Board::parse_flexible(s).unwrap();
+        let solved = solver::solve(&board).map(|b| b.to_line());
+        assert_eq!(solved.as_deref(), Some(solution), "{name}: wrong solution");
+    }
+}
+
+#[test]
+fn fixtures_are_unique() {
+    for &(name, s, _, _) in FIXTURES {
+        let board = Board::parse_flexible(s).unwrap();
+        assert_eq!(solver::count_solutions(&board, 2), 1, "{name}: not unique");
+    }
+}
+
+#[test]
+fn fixtures_have_known_ratings() {
+    for &(name, s, _, difficulty) in FIXTURES {
+        let board = Board::parse_flexible(s).unwrap();
+        assert_eq!(techniques::rate(&board), difficulty, "{name}: wrong rating");
+    }
+}